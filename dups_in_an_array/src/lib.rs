@@ -0,0 +1,6 @@
+//! Helpers for finding duplicate records.
+//!
+//! Exact duplicates of integers are handled in the binary; this library holds the
+//! fuzzy matching used for string records that are "almost" the same.
+
+pub mod near_duplicates;
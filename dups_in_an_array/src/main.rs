@@ -1,3 +1,4 @@
+use dups_in_an_array::near_duplicates::NearDuplicateDetector;
 use std::collections::{HashMap, HashSet};
 
 /// Functions to find duplicates and their frequencies in a vector.
@@ -44,6 +45,22 @@ fn main() {
     let nums = vec![4, 3, 2, 7, 8, 2, 3, 1];
     println!("{:?}", find_duplicates(&nums)); // [2, 3]
     println!("{:?}", find_duplicates_frequency(nums.clone())); // {4: 1, 3: 2, 2: 2, 7: 1, 8: 1, 1: 1}
+
+    let customers = vec![
+        "John Smith, 12 Main Street",
+        "Jon Smith, 12 Main Street",
+        "Alice Jones, 4 Elm Road",
+    ];
+    let detector = NearDuplicateDetector::new(3, 20, 5, 0.6);
+    for pair in detector.find_near_duplicates(&customers) {
+        println!(
+            "{:?} ~ {:?} (estimated {:.2}, jaccard {:.2})",
+            customers[pair.left],
+            customers[pair.right],
+            pair.estimated,
+            pair.jaccard.unwrap_or_default()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,342 @@
+//! Near-duplicate detection for string records using MinHash and LSH banding.
+//!
+//! Exact equality misses records such as `"Jon Smith, 12 Main St"` and
+//! `"John Smith, 12 Main Street"`. This module compares records by the Jaccard
+//! similarity of their character shingles instead:
+//!
+//! 1. Each record is normalised and split into overlapping character k-shingles.
+//! 2. A MinHash signature approximates the shingle set with a fixed number of hashes.
+//! 3. Signatures are cut into bands; records sharing any band bucket become candidates.
+//! 4. Candidates whose estimated similarity comes within a slack of the threshold are
+//!    verified against the exact Jaccard similarity of their shingle sets.
+//!
+//! # Examples
+//!
+//! ```
+//! use dups_in_an_array::near_duplicates::NearDuplicateDetector;
+//!
+//! let records = vec![
+//!     "John Smith, 12 Main Street",
+//!     "Jon Smith, 12 Main Street",
+//!     "Alice Jones, 4 Elm Road",
+//! ];
+//!
+//! let detector = NearDuplicateDetector::new(3, 20, 5, 0.6);
+//! let pairs = detector.find_near_duplicates(&records);
+//! assert_eq!(pairs.len(), 1);
+//! assert_eq!((pairs[0].left, pairs[0].right), (0, 1));
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// A pair of records that look like near duplicates.
+///
+/// `left` and `right` are indices into the input slice, with `left < right`.
+/// `jaccard` is only filled in once the pair has been through the verification pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidatePair {
+    pub left: usize,
+    pub right: usize,
+    pub estimated: f64,
+    pub jaccard: Option<f64>,
+}
+
+/// Splits a record into its set of overlapping character k-shingles.
+///
+/// The record is lowercased and runs of whitespace are collapsed to a single space
+/// first, so formatting differences do not count against similarity. Records shorter
+/// than `k` characters produce a single shingle containing the whole record.
+///
+/// # Examples
+///
+/// ```
+/// use dups_in_an_array::near_duplicates::shingles;
+///
+/// let set = shingles("Abcd", 3);
+/// assert_eq!(set.len(), 2);
+/// assert!(set.contains("abc") && set.contains("bcd"));
+/// ```
+pub fn shingles(record: &str, k: usize) -> HashSet<String> {
+    let normalised = record
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let chars: Vec<char> = normalised.chars().collect();
+
+    if chars.is_empty() {
+        return HashSet::new();
+    }
+    if k == 0 || chars.len() <= k {
+        return HashSet::from([normalised]);
+    }
+    chars.windows(k).map(|w| w.iter().collect()).collect()
+}
+
+/// Computes the exact Jaccard similarity `|A ∩ B| / |A ∪ B|` of two sets.
+///
+/// Two empty sets are considered identical.
+pub fn jaccard<T: Hash + Eq>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
+
+/// Produces fixed-length MinHash signatures for shingle sets.
+///
+/// Each of the `num_hashes` slots uses an independently seeded hash; the slot holds the
+/// minimum hash over all shingles. The fraction of slots on which two signatures agree
+/// is an unbiased estimate of the Jaccard similarity of the underlying sets.
+#[derive(Debug, Clone)]
+pub struct MinHasher {
+    seeds: Vec<u64>,
+}
+
+impl MinHasher {
+    pub fn new(num_hashes: usize) -> Self {
+        // Spread the seeds out with a splitmix-style constant so neighbouring slots differ.
+        let seeds = (0..num_hashes as u64)
+            .map(|i| i.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        MinHasher { seeds }
+    }
+
+    pub fn num_hashes(&self) -> usize {
+        self.seeds.len()
+    }
+
+    pub fn signature(&self, shingles: &HashSet<String>) -> Vec<u64> {
+        self.seeds
+            .iter()
+            .map(|&seed| {
+                shingles
+                    .iter()
+                    .map(|shingle| {
+                        let mut hasher = DefaultHasher::new();
+                        seed.hash(&mut hasher);
+                        shingle.hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+}
+
+/// Estimates the Jaccard similarity of two sets from their MinHash signatures.
+pub fn estimate_similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    let matching = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    matching as f64 / a.len() as f64
+}
+
+/// Finds near-duplicate string records with MinHash signatures and LSH banding.
+///
+/// The signature length is `bands * rows`. More rows per band make a bucket collision
+/// stricter; more bands give more chances to collide. Pairs whose similarity is close to
+/// `(1 / bands) ^ (1 / rows)` have roughly even odds of becoming candidates.
+#[derive(Debug, Clone)]
+pub struct NearDuplicateDetector {
+    shingle_size: usize,
+    bands: usize,
+    rows: usize,
+    threshold: f64,
+    slack: f64,
+    hasher: MinHasher,
+}
+
+impl NearDuplicateDetector {
+    pub fn new(shingle_size: usize, bands: usize, rows: usize, threshold: f64) -> Self {
+        assert!(bands > 0 && rows > 0, "bands and rows must be non-zero");
+        NearDuplicateDetector {
+            shingle_size,
+            bands,
+            rows,
+            threshold,
+            slack: 1.0 / ((bands * rows) as f64).sqrt(),
+            hasher: MinHasher::new(bands * rows),
+        }
+    }
+
+    /// Sets how far below the threshold a pair's estimated similarity may fall and still
+    /// be passed on to [`verify`](NearDuplicateDetector::verify).
+    ///
+    /// The estimate is noisy, so filtering on the threshold itself would lose true pairs
+    /// for good. The default, `1 / sqrt(bands * rows)`, is two standard errors of the
+    /// estimate at its noisiest; a larger slack verifies more pairs for better recall, and
+    /// `0.0` trusts the estimate outright.
+    pub fn slack(mut self, slack: f64) -> Self {
+        self.slack = slack.max(0.0);
+        self
+    }
+
+    /// Returns candidate pairs whose estimated similarity is at least the threshold minus
+    /// the [`slack`](NearDuplicateDetector::slack).
+    ///
+    /// Only pairs that share at least one LSH bucket are compared, so the cost grows with
+    /// the number of collisions rather than with every pair of records.
+    pub fn candidates<S: AsRef<str>>(&self, records: &[S]) -> Vec<CandidatePair> {
+        let signatures: Vec<Vec<u64>> = records
+            .iter()
+            .map(|r| {
+                self.hasher
+                    .signature(&shingles(r.as_ref(), self.shingle_size))
+            })
+            .collect();
+
+        let mut pairs = HashSet::new();
+        for band in 0..self.bands {
+            let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+            for (i, signature) in signatures.iter().enumerate() {
+                let slice = &signature[band * self.rows..(band + 1) * self.rows];
+                buckets.entry(slice).or_default().push(i);
+            }
+            for members in buckets.values().filter(|m| m.len() > 1) {
+                for (x, &left) in members.iter().enumerate() {
+                    for &right in &members[x + 1..] {
+                        pairs.insert((left, right));
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<CandidatePair> = pairs
+            .into_iter()
+            .map(|(left, right)| CandidatePair {
+                left,
+                right,
+                estimated: estimate_similarity(&signatures[left], &signatures[right]),
+                jaccard: None,
+            })
+            .filter(|pair| pair.estimated >= self.threshold - self.slack)
+            .collect();
+        candidates.sort_by_key(|pair| (pair.left, pair.right));
+        candidates
+    }
+
+    /// Recomputes the exact Jaccard similarity of each candidate and drops false positives.
+    pub fn verify<S: AsRef<str>>(
+        &self,
+        records: &[S],
+        candidates: Vec<CandidatePair>,
+    ) -> Vec<CandidatePair> {
+        let mut cache: HashMap<usize, HashSet<String>> = HashMap::new();
+        let mut verified = vec![];
+
+        for mut pair in candidates {
+            for index in [pair.left, pair.right] {
+                cache
+                    .entry(index)
+                    .or_insert_with(|| shingles(records[index].as_ref(), self.shingle_size));
+            }
+            let similarity = jaccard(&cache[&pair.left], &cache[&pair.right]);
+            if similarity >= self.threshold {
+                pair.jaccard = Some(similarity);
+                verified.push(pair);
+            }
+        }
+        verified
+    }
+
+    /// Runs candidate generation followed by the exact-Jaccard verification pass.
+    pub fn find_near_duplicates<S: AsRef<str>>(&self, records: &[S]) -> Vec<CandidatePair> {
+        let candidates = self.candidates(records);
+        self.verify(records, candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shingles_normalises_case_and_whitespace() {
+        assert_eq!(shingles("Main  St", 3), shingles("main st", 3));
+        assert_eq!(shingles("ab", 3), HashSet::from(["ab".to_string()]));
+        assert!(shingles("   ", 3).is_empty());
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let a = HashSet::from([1, 2, 3]);
+        let b = HashSet::from([2, 3, 4]);
+        assert_eq!(jaccard(&a, &b), 0.5);
+        assert_eq!(jaccard::<i32>(&HashSet::new(), &HashSet::new()), 1.0);
+    }
+
+    #[test]
+    fn test_minhash_estimate_tracks_jaccard() {
+        let hasher = MinHasher::new(256);
+        let a = shingles("the quick brown fox jumps over the lazy dog", 3);
+        let b = shingles("the quick brown fox jumped over the lazy dogs", 3);
+        let estimate = estimate_similarity(&hasher.signature(&a), &hasher.signature(&b));
+        assert!((estimate - jaccard(&a, &b)).abs() < 0.1);
+        assert_eq!(
+            estimate_similarity(&hasher.signature(&a), &hasher.signature(&a)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let records = [
+            "Acme Corporation, 100 Market Street",
+            "Globex Inc, 7 Harbour Way",
+            "ACME Corporation,  100 Market St",
+            "Initech LLC, 4 Office Park",
+            "Globex Inc., 7 Harbour Way",
+        ];
+        let detector = NearDuplicateDetector::new(3, 25, 4, 0.6);
+        let pairs: Vec<_> = detector
+            .find_near_duplicates(&records)
+            .iter()
+            .map(|p| (p.left, p.right))
+            .collect();
+        assert_eq!(pairs, vec![(0, 2), (1, 4)]);
+    }
+
+    #[test]
+    fn test_slack_keeps_pairs_the_estimate_undersells() {
+        // Each record differs from its partner in one word, putting the exact similarity
+        // around the threshold, where a short signature often misjudges it.
+        let records: Vec<String> = (0..60)
+            .flat_map(|i| {
+                [
+                    format!("customer {} lives at {} long street", i, i * 7),
+                    format!("customer {} lives at {} long road", i, i * 7),
+                ]
+            })
+            .collect();
+        let found = |detector: NearDuplicateDetector| {
+            detector
+                .find_near_duplicates(&records)
+                .iter()
+                .filter(|p| p.left / 2 == p.right / 2)
+                .count()
+        };
+        let strict = found(NearDuplicateDetector::new(3, 16, 1, 0.7).slack(0.0));
+        let relaxed = found(NearDuplicateDetector::new(3, 16, 1, 0.7));
+        assert!(strict < relaxed, "strict {} relaxed {}", strict, relaxed);
+    }
+
+    #[test]
+    fn test_verify_drops_false_positives() {
+        let records = ["abcdef", "uvwxyz"];
+        let detector = NearDuplicateDetector::new(2, 1, 1, 0.5);
+        let forced = vec![CandidatePair {
+            left: 0,
+            right: 1,
+            estimated: 1.0,
+            jaccard: None,
+        }];
+        assert!(detector.verify(&records, forced).is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+//! Exporters for [`StateMachine`]s: Graphviz DOT, Mermaid and JSON snapshots.
+//!
+//! The diagrams are rendered from the transition table, with states and events labelled
+//! by their `Debug` output, so any enum that derives `Debug` can be drawn. Snapshots
+//! capture the current state and the transition history and can be restored into a
+//! machine built from the same table.
+//!
+//! # Examples
+//!
+//! ```
+//! use enum_pattern_match::{traffic_light_machine, TrafficLightEvent};
+//!
+//! let mut light = traffic_light_machine();
+//! light.fire(TrafficLightEvent::TimerElapsed).unwrap();
+//!
+//! assert!(light.to_dot("TrafficLight").contains("\"Red\" -> \"Green\" [label=\"TimerElapsed\"];"));
+//! assert!(light.to_mermaid().contains("Red --> Green : TimerElapsed"));
+//!
+//! let json = light.to_json().unwrap();
+//! let mut restored = traffic_light_machine();
+//! restored.restore_json(&json).unwrap();
+//! assert_eq!(restored.state(), light.state());
+//! assert_eq!(restored.history(), light.history());
+//! ```
+
+use crate::state_machine::{StateMachine, Transition, TransitionError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// The serialisable part of a machine: where it is and how it got there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot<S, E> {
+    pub state: S,
+    pub history: Vec<Transition<S, E>>,
+}
+
+/// Error returned when a snapshot cannot be restored into a machine.
+#[derive(Debug)]
+pub enum RestoreError<S, E> {
+    /// The JSON could not be parsed into a snapshot.
+    Json(serde_json::Error),
+    /// The history contains a transition the machine's table does not allow.
+    Illegal(TransitionError<S, E>),
+    /// The history does not chain up, or does not end in the recorded state.
+    Inconsistent { expected: S, found: S },
+}
+
+impl<S: Debug, E: Debug> fmt::Display for RestoreError<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestoreError::Json(e) => write!(f, "invalid snapshot JSON: {}", e),
+            RestoreError::Illegal(e) => write!(f, "snapshot history is not legal: {}", e),
+            RestoreError::Inconsistent { expected, found } => write!(
+                f,
+                "snapshot is inconsistent: expected state {:?}, found {:?}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl<S: Debug, E: Debug> Error for RestoreError<S, E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RestoreError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<S, E> StateMachine<S, E>
+where
+    S: Clone + Eq + Hash + Debug,
+    E: Clone + Eq + Hash + Debug,
+{
+    /// Renders the transition table as a Graphviz `digraph`.
+    ///
+    /// The initial state is reached from a point-shaped start node and the current state is
+    /// drawn bold.
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", quote_dot(name));
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    __start [shape=point];\n");
+        dot.push_str(&format!(
+            "    __start -> {};\n",
+            quote_dot(&label(self.initial()))
+        ));
+        for state in self.states() {
+            let style = if state == self.state() {
+                " [style=bold]"
+            } else {
+                ""
+            };
+            dot.push_str(&format!("    {}{};\n", quote_dot(&label(state)), style));
+        }
+        for t in self.transitions() {
+            dot.push_str(&format!(
+                "    {} -> {} [label={}];\n",
+                quote_dot(&label(&t.from)),
+                quote_dot(&label(&t.to)),
+                quote_dot(&label(&t.event))
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the transition table as a Mermaid `stateDiagram-v2`.
+    ///
+    /// The current state is highlighted with a `current` class.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("stateDiagram-v2\n");
+        let ids = self.mermaid_ids();
+        for state in self.states() {
+            let text = label(state);
+            let id = &ids[state];
+            if *id != text {
+                let text = text.replace('"', "#quot;");
+                mermaid.push_str(&format!("    state \"{}\" as {}\n", text, id));
+            }
+        }
+        mermaid.push_str(&format!("    [*] --> {}\n", ids[self.initial()]));
+        for t in self.transitions() {
+            mermaid.push_str(&format!(
+                "    {} --> {} : {}\n",
+                ids[&t.from],
+                ids[&t.to],
+                label(&t.event).replace(':', " ")
+            ));
+        }
+        mermaid.push_str("    classDef current font-weight:bold,stroke-width:3px\n");
+        mermaid.push_str(&format!("    class {} current\n", ids[self.state()]));
+        mermaid
+    }
+
+    /// A distinct Mermaid id for every state: its label with anything but ASCII letters
+    /// and digits replaced by `_`, and a numeric suffix if another state already has that
+    /// id, so that e.g. `Go-Left` and `Go_Left` stay separate nodes.
+    fn mermaid_ids(&self) -> HashMap<&S, String> {
+        let mut ids = HashMap::new();
+        let mut taken = HashSet::new();
+        for state in self.states() {
+            let base = mermaid_id(&label(state));
+            let mut id = base.clone();
+            let mut suffix = 1;
+            while !taken.insert(id.clone()) {
+                suffix += 1;
+                id = format!("{}_{}", base, suffix);
+            }
+            ids.insert(state, id);
+        }
+        ids
+    }
+
+    /// Every state mentioned by the table, in order of first appearance.
+    fn states(&self) -> Vec<&S> {
+        let mut states = vec![self.initial()];
+        for t in self.transitions() {
+            for state in [&t.from, &t.to] {
+                if !states.contains(&state) {
+                    states.push(state);
+                }
+            }
+        }
+        states
+    }
+
+    pub fn snapshot(&self) -> Snapshot<S, E> {
+        Snapshot {
+            state: self.state().clone(),
+            history: self.history().to_vec(),
+        }
+    }
+
+    /// Restores the state and history from a snapshot.
+    ///
+    /// The history is replayed against the transition table from the initial state, so a
+    /// snapshot taken from a machine with a different table is rejected. Hooks are not run.
+    pub fn restore(&mut self, snapshot: Snapshot<S, E>) -> Result<(), RestoreError<S, E>> {
+        let mut current = self.initial().clone();
+        for t in &snapshot.history {
+            if t.from != current {
+                return Err(RestoreError::Inconsistent {
+                    expected: current,
+                    found: t.from.clone(),
+                });
+            }
+            if self.target(&t.from, &t.event) != Some(&t.to) {
+                return Err(RestoreError::Illegal(TransitionError::Illegal {
+                    from: t.from.clone(),
+                    event: t.event.clone(),
+                }));
+            }
+            current = t.to.clone();
+        }
+        if snapshot.state != current {
+            return Err(RestoreError::Inconsistent {
+                expected: current,
+                found: snapshot.state,
+            });
+        }
+        self.replace(snapshot.state, snapshot.history);
+        Ok(())
+    }
+}
+
+impl<S, E> StateMachine<S, E>
+where
+    S: Clone + Eq + Hash + Debug + Serialize + DeserializeOwned,
+    E: Clone + Eq + Hash + Debug + Serialize + DeserializeOwned,
+{
+    /// Serialises the current state and history as pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.snapshot())
+    }
+
+    /// Parses a JSON snapshot produced by [`to_json`](StateMachine::to_json) and restores it.
+    pub fn restore_json(&mut self, json: &str) -> Result<(), RestoreError<S, E>> {
+        let snapshot = serde_json::from_str(json).map_err(RestoreError::Json)?;
+        self.restore(snapshot)
+    }
+}
+
+fn label<T: Debug>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+fn quote_dot(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_id(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::traffic_light::{traffic_light_machine, TrafficLight, TrafficLightEvent};
+    use crate::StateMachine;
+
+    use super::*;
+
+    #[test]
+    fn test_traffic_light_dot() {
+        let light = traffic_light_machine();
+        assert_eq!(
+            light.to_dot("TrafficLight"),
+            "digraph \"TrafficLight\" {\n\
+             \x20   rankdir=LR;\n\
+             \x20   __start [shape=point];\n\
+             \x20   __start -> \"Red\";\n\
+             \x20   \"Red\" [style=bold];\n\
+             \x20   \"Green\";\n\
+             \x20   \"Yellow\";\n\
+             \x20   \"Red\" -> \"Green\" [label=\"TimerElapsed\"];\n\
+             \x20   \"Green\" -> \"Yellow\" [label=\"TimerElapsed\"];\n\
+             \x20   \"Yellow\" -> \"Red\" [label=\"TimerElapsed\"];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_traffic_light_mermaid_marks_current_state() {
+        let mut light = traffic_light_machine();
+        light.fire(TrafficLightEvent::TimerElapsed).unwrap();
+        let mermaid = light.to_mermaid();
+        assert!(mermaid.starts_with("stateDiagram-v2\n    [*] --> Red\n"));
+        assert!(mermaid.contains("    Yellow --> Red : TimerElapsed\n"));
+        assert!(mermaid.ends_with("    class Green current\n"));
+    }
+
+    #[test]
+    fn test_labels_with_data_are_sanitised() {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        enum Stage {
+            Load(u8),
+            Named(&'static str),
+        }
+        let machine = StateMachine::new(Stage::Load(1), [(Stage::Load(1), 'x', Stage::Named("q"))]);
+
+        let dot = machine.to_dot("stages");
+        assert!(dot.contains("    \"Load(1)\" -> \"Named(\\\"q\\\")\" [label=\"'x'\"];\n"));
+
+        let mermaid = machine.to_mermaid();
+        assert!(mermaid.contains("    state \"Load(1)\" as Load_1_\n"));
+        assert!(mermaid.contains("    state \"Named(#quot;q#quot;)\" as Named__q__\n"));
+        assert!(mermaid.contains("    Load_1_ --> Named__q__ : 'x'\n"));
+    }
+
+    #[test]
+    fn test_mermaid_ids_do_not_collide() {
+        let machine = StateMachine::new(
+            "Go-Left",
+            [("Go-Left", 1, "Go_Left"), ("Go_Left", 2, "Go_Left_2")],
+        );
+        let mermaid = machine.to_mermaid();
+        assert!(mermaid.contains("    state \"#quot;Go-Left#quot;\" as _Go_Left_\n"));
+        assert!(mermaid.contains("    _Go_Left_ --> _Go_Left__2 : 1\n"));
+        assert!(mermaid.contains("    _Go_Left__2 --> _Go_Left_2_ : 2\n"));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut light = traffic_light_machine();
+        for _ in 0..4 {
+            light.fire(TrafficLightEvent::TimerElapsed).unwrap();
+        }
+        let json = light.to_json().unwrap();
+        assert!(json.contains("\"state\": \"Green\""));
+
+        let mut restored = traffic_light_machine();
+        restored.restore_json(&json).unwrap();
+        assert_eq!(restored.snapshot(), light.snapshot());
+    }
+
+    #[test]
+    fn test_restore_rejects_tampered_snapshots() {
+        let mut light = traffic_light_machine();
+        let skipped = Snapshot {
+            state: TrafficLight::Yellow,
+            history: vec![Transition {
+                from: TrafficLight::Red,
+                event: TrafficLightEvent::TimerElapsed,
+                to: TrafficLight::Yellow,
+            }],
+        };
+        assert!(matches!(
+            light.restore(skipped),
+            Err(RestoreError::Illegal(_))
+        ));
+
+        let wrong_state = Snapshot {
+            state: TrafficLight::Yellow,
+            history: vec![],
+        };
+        assert!(matches!(
+            light.restore(wrong_state),
+            Err(RestoreError::Inconsistent {
+                expected: TrafficLight::Red,
+                found: TrafficLight::Yellow
+            })
+        ));
+
+        assert!(matches!(
+            light.restore_json("{"),
+            Err(RestoreError::Json(_))
+        ));
+        assert_eq!(light.state(), &TrafficLight::Red);
+    }
+}
@@ -3,16 +3,15 @@
 // use enum_pattern_match::TrafficLight;
 // enum_pattern_match::print_light_color(TrafficLight::Red);
 // ```
-pub enum TrafficLight {
-    Red,
-    Green,
-    Yellow,
-}
+pub mod export;
+pub mod model_check;
+pub mod state_machine;
+pub mod traffic_light;
 
-pub fn print_light_color(light: TrafficLight) {
-    match light {
-        TrafficLight::Red => println!("Red"),
-        TrafficLight::Green => println!("Green"),
-        TrafficLight::Yellow => println!("Yellow"),
-    }
-}
+pub use export::{RestoreError, Snapshot};
+pub use model_check::{CheckStats, Counterexample, ModelChecker};
+pub use state_machine::{StateMachine, Transition, TransitionError};
+pub use traffic_light::{
+    print_light_color, traffic_light_machine, TrafficLight, TrafficLightController,
+    TrafficLightEvent,
+};
@@ -1,7 +1,37 @@
 use enum_pattern_match::print_light_color;
+use enum_pattern_match::ModelChecker;
 use enum_pattern_match::TrafficLight;
+use enum_pattern_match::TrafficLightController;
+use std::time::Duration;
 
 fn main() {
     let light = TrafficLight::Red;
     print_light_color(light);
+
+    let mut controller = TrafficLightController::new();
+    controller
+        .machine_mut()
+        .on_enter(TrafficLight::Yellow, |_| println!("Prepare to stop"));
+    for _ in 0..6 {
+        controller.advance(Duration::from_secs(10));
+        print_light_color(controller.light());
+    }
+    println!("History: {:?}", controller.machine().history());
+
+    println!("{}", controller.machine().to_dot("TrafficLight"));
+    println!("{}", controller.machine().to_mermaid());
+    match controller.machine().to_json() {
+        Ok(json) => println!("{}", json),
+        Err(e) => println!("Error serialising state: {}", e),
+    }
+
+    let verdict = ModelChecker::new(controller.machine(), 10)
+        .transition_invariant("never Red directly after Green", |t| {
+            !(t.from == TrafficLight::Green && t.to == TrafficLight::Red)
+        })
+        .check();
+    match verdict {
+        Ok(stats) => println!("Model check passed: {:?}", stats),
+        Err(counterexample) => println!("{}", counterexample),
+    }
 }
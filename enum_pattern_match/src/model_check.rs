@@ -0,0 +1,310 @@
+//! Bounded model checking of [`StateMachine`] transition tables.
+//!
+//! [`ModelChecker`] explores every event sequence the table allows from the machine's
+//! initial state, up to a maximum depth, and checks user-supplied invariants along the way.
+//! State invariants look at each reachable state; transition invariants look at each step,
+//! which is what properties such as "never Green directly after Red" need.
+//!
+//! The search is breadth-first, so the first violation found has the shortest possible
+//! trace. Invariants only depend on the current state or step, so a state reached again
+//! by a longer path has nothing new to check and is not expanded twice.
+//!
+//! # Examples
+//!
+//! ```
+//! use enum_pattern_match::model_check::ModelChecker;
+//! use enum_pattern_match::{traffic_light_machine, TrafficLight};
+//!
+//! let light = traffic_light_machine();
+//! let stats = ModelChecker::new(&light, 10)
+//!     .transition_invariant("never Red directly after Green", |t| {
+//!         !(t.from == TrafficLight::Green && t.to == TrafficLight::Red)
+//!     })
+//!     .check()
+//!     .unwrap();
+//! assert_eq!(stats.states, 3);
+//! ```
+
+use crate::state_machine::{StateMachine, Transition};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+type StateInvariant<'a, S> = Box<dyn Fn(&S) -> bool + 'a>;
+type TransitionInvariant<'a, S, E> = Box<dyn Fn(&Transition<S, E>) -> bool + 'a>;
+/// For every reached state: its depth and the transition that first reached it.
+type Reached<S, E> = HashMap<S, (usize, Option<Transition<S, E>>)>;
+
+/// Explores a machine's transition table and checks invariants up to a fixed depth.
+pub struct ModelChecker<'a, S, E> {
+    machine: &'a StateMachine<S, E>,
+    max_depth: usize,
+    state_invariants: Vec<(String, StateInvariant<'a, S>)>,
+    transition_invariants: Vec<(String, TransitionInvariant<'a, S, E>)>,
+}
+
+/// What the checker covered when no invariant failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckStats {
+    /// Distinct states reached, including the initial one.
+    pub states: usize,
+    /// Transitions checked.
+    pub transitions: usize,
+    /// Length of the longest shortest path explored.
+    pub depth: usize,
+}
+
+/// The shortest event sequence that breaks an invariant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample<S, E> {
+    pub invariant: String,
+    pub trace: Vec<Transition<S, E>>,
+}
+
+impl<S: Debug, E: Debug> fmt::Display for Counterexample<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invariant {:?} violated", self.invariant)?;
+        if self.trace.is_empty() {
+            return write!(f, " in the initial state");
+        }
+        write!(f, " after {} step(s):", self.trace.len())?;
+        for t in &self.trace {
+            write!(f, "\n  {:?} --{:?}--> {:?}", t.from, t.event, t.to)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, S, E> ModelChecker<'a, S, E>
+where
+    S: Clone + Eq + Hash,
+    E: Clone + Eq + Hash,
+{
+    pub fn new(machine: &'a StateMachine<S, E>, max_depth: usize) -> Self {
+        ModelChecker {
+            machine,
+            max_depth,
+            state_invariants: vec![],
+            transition_invariants: vec![],
+        }
+    }
+
+    /// Adds a check that must hold in every reachable state.
+    pub fn invariant<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&S) -> bool + 'a,
+    {
+        self.state_invariants.push((name.into(), Box::new(check)));
+        self
+    }
+
+    /// Adds a check that must hold for every reachable transition.
+    pub fn transition_invariant<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&Transition<S, E>) -> bool + 'a,
+    {
+        self.transition_invariants
+            .push((name.into(), Box::new(check)));
+        self
+    }
+
+    /// Runs the breadth-first search from the machine's initial state.
+    pub fn check(&self) -> Result<CheckStats, Counterexample<S, E>> {
+        let initial = self.machine.initial().clone();
+        let mut reached: Reached<S, E> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut stats = CheckStats {
+            states: 1,
+            transitions: 0,
+            depth: 0,
+        };
+
+        if let Some(name) = self.failing_state_invariant(&initial) {
+            return Err(Counterexample {
+                invariant: name.to_string(),
+                trace: vec![],
+            });
+        }
+        reached.insert(initial.clone(), (0, None));
+        queue.push_back(initial);
+
+        while let Some(state) = queue.pop_front() {
+            let depth = reached[&state].0;
+            if depth == self.max_depth {
+                continue;
+            }
+            for t in self
+                .machine
+                .transitions()
+                .iter()
+                .filter(|t| t.from == state)
+            {
+                stats.transitions += 1;
+                if let Some(name) = self.failing_transition_invariant(t) {
+                    return Err(self.counterexample(name, &reached, t));
+                }
+                if reached.contains_key(&t.to) {
+                    continue;
+                }
+                if let Some(name) = self.failing_state_invariant(&t.to) {
+                    return Err(self.counterexample(name, &reached, t));
+                }
+                reached.insert(t.to.clone(), (depth + 1, Some(t.clone())));
+                queue.push_back(t.to.clone());
+                stats.states += 1;
+                stats.depth = depth + 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    fn failing_state_invariant(&self, state: &S) -> Option<&str> {
+        self.state_invariants
+            .iter()
+            .find(|(_, check)| !check(state))
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn failing_transition_invariant(&self, transition: &Transition<S, E>) -> Option<&str> {
+        self.transition_invariants
+            .iter()
+            .find(|(_, check)| !check(transition))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Walks the parent links back from `last` to rebuild the trace from the initial state.
+    fn counterexample(
+        &self,
+        name: &str,
+        reached: &Reached<S, E>,
+        last: &Transition<S, E>,
+    ) -> Counterexample<S, E> {
+        let mut trace = vec![last.clone()];
+        let mut state = &last.from;
+        while let Some((_, Some(t))) = reached.get(state) {
+            trace.push(t.clone());
+            state = &t.from;
+        }
+        trace.reverse();
+        Counterexample {
+            invariant: name.to_string(),
+            trace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traffic_light::{traffic_light_machine, TrafficLight};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Event {
+        Timer,
+        Override,
+    }
+
+    /// A traffic light with a manual override that jumps straight from Red to Green.
+    fn overridable_light() -> StateMachine<TrafficLight, Event> {
+        use TrafficLight::*;
+        StateMachine::new(
+            Red,
+            [
+                (Red, Event::Timer, Yellow),
+                (Yellow, Event::Timer, Green),
+                (Green, Event::Timer, Yellow),
+                (Yellow, Event::Override, Red),
+                (Red, Event::Override, Green),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_traffic_light_satisfies_invariants() {
+        let light = traffic_light_machine();
+        let stats = ModelChecker::new(&light, 20)
+            .invariant("is a known colour", |_| true)
+            .transition_invariant("never Red directly after Green", |t| {
+                !(t.from == TrafficLight::Green && t.to == TrafficLight::Red)
+            })
+            .check()
+            .unwrap();
+        assert_eq!(
+            stats,
+            CheckStats {
+                states: 3,
+                transitions: 3,
+                depth: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_reports_shortest_transition_counterexample() {
+        let light = overridable_light();
+        let err = ModelChecker::new(&light, 5)
+            .transition_invariant("never Green directly after Red", |t| {
+                !(t.from == TrafficLight::Red && t.to == TrafficLight::Green)
+            })
+            .check()
+            .unwrap_err();
+        assert_eq!(err.invariant, "never Green directly after Red");
+        assert_eq!(
+            err.trace,
+            vec![Transition {
+                from: TrafficLight::Red,
+                event: Event::Override,
+                to: TrafficLight::Green
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reports_shortest_state_counterexample() {
+        let light = overridable_light();
+        let err = ModelChecker::new(&light, 5)
+            .invariant("never Green", |s| *s != TrafficLight::Green)
+            .check()
+            .unwrap_err();
+        assert_eq!(err.trace.len(), 1);
+        assert_eq!(
+            err.to_string(),
+            "invariant \"never Green\" violated after 1 step(s):\n  Red --Override--> Green"
+        );
+    }
+
+    #[test]
+    fn test_depth_bound_limits_search() {
+        let light = overridable_light();
+        let check = |depth| {
+            ModelChecker::new(&light, depth)
+                .transition_invariant("never Yellow after Green", |t| {
+                    !(t.from == TrafficLight::Green && t.to == TrafficLight::Yellow)
+                })
+                .check()
+        };
+
+        assert!(check(1).is_ok());
+        let events: Vec<_> = check(2)
+            .unwrap_err()
+            .trace
+            .iter()
+            .map(|t| t.event)
+            .collect();
+        assert_eq!(events, vec![Event::Override, Event::Timer]);
+    }
+
+    #[test]
+    fn test_initial_state_violation() {
+        let light = traffic_light_machine();
+        let err = ModelChecker::new(&light, 3)
+            .invariant("never Red", |s| *s != TrafficLight::Red)
+            .check()
+            .unwrap_err();
+        assert!(err.trace.is_empty());
+        assert_eq!(
+            err.to_string(),
+            "invariant \"never Red\" violated in the initial state"
+        );
+    }
+}
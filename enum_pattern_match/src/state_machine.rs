@@ -0,0 +1,319 @@
+//! A small, data-driven state machine over enum states and events.
+//!
+//! The legal moves are declared up front as a transition table of `(from, event, to)`
+//! rows. Firing an event that has no row for the current state is rejected with a
+//! [`TransitionError`] instead of silently doing nothing, so illegal lifecycles surface
+//! as errors at the call site.
+//!
+//! # Examples
+//!
+//! ```
+//! use enum_pattern_match::state_machine::{StateMachine, TransitionError};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+//! enum Job { Pending, Running, Succeeded, Failed, Retrying }
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+//! enum Event { Start, Finish, Fail, Retry }
+//!
+//! let mut job = StateMachine::new(
+//!     Job::Pending,
+//!     [
+//!         (Job::Pending, Event::Start, Job::Running),
+//!         (Job::Running, Event::Finish, Job::Succeeded),
+//!         (Job::Running, Event::Fail, Job::Failed),
+//!         (Job::Failed, Event::Retry, Job::Retrying),
+//!         (Job::Retrying, Event::Start, Job::Running),
+//!     ],
+//! );
+//!
+//! assert_eq!(job.fire(Event::Start), Ok(&Job::Running));
+//! assert_eq!(
+//!     job.fire(Event::Retry),
+//!     Err(TransitionError::Illegal { from: Job::Running, event: Event::Retry })
+//! );
+//! assert_eq!(job.history().len(), 1);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+/// One row of a transition table, also used as an entry in the transition history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition<S, E> {
+    pub from: S,
+    pub event: E,
+    pub to: S,
+}
+
+/// Error returned when an event is fired that the current state does not accept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionError<S, E> {
+    Illegal { from: S, event: E },
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for TransitionError<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::Illegal { from, event } => {
+                write!(
+                    f,
+                    "illegal transition: {:?} does not accept {:?}",
+                    from, event
+                )
+            }
+        }
+    }
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> Error for TransitionError<S, E> {}
+
+type Hook<S, E> = Box<dyn FnMut(&Transition<S, E>)>;
+
+/// A state machine driven by a declared transition table.
+///
+/// Hooks registered with [`on_enter`](StateMachine::on_enter) and
+/// [`on_exit`](StateMachine::on_exit) run on every successful transition, exit hooks of
+/// the old state first. Every successful transition is appended to the history.
+pub struct StateMachine<S, E> {
+    initial: S,
+    state: S,
+    table: Vec<Transition<S, E>>,
+    index: HashMap<(S, E), usize>,
+    on_enter: HashMap<S, Vec<Hook<S, E>>>,
+    on_exit: HashMap<S, Vec<Hook<S, E>>>,
+    history: Vec<Transition<S, E>>,
+}
+
+impl<S, E> StateMachine<S, E>
+where
+    S: Clone + Eq + Hash,
+    E: Clone + Eq + Hash,
+{
+    /// Creates a machine in `initial` accepting the given `(from, event, to)` rows.
+    ///
+    /// If the same `(from, event)` pair is declared twice, the last row wins.
+    pub fn new<I>(initial: S, transitions: I) -> Self
+    where
+        I: IntoIterator<Item = (S, E, S)>,
+    {
+        let mut table: Vec<Transition<S, E>> = vec![];
+        let mut index = HashMap::new();
+        for (from, event, to) in transitions {
+            let row = Transition { from, event, to };
+            match index.get(&(row.from.clone(), row.event.clone())) {
+                Some(&i) => table[i] = row,
+                None => {
+                    index.insert((row.from.clone(), row.event.clone()), table.len());
+                    table.push(row);
+                }
+            }
+        }
+        StateMachine {
+            state: initial.clone(),
+            initial,
+            table,
+            index,
+            on_enter: HashMap::new(),
+            on_exit: HashMap::new(),
+            history: vec![],
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// The state the machine was created in.
+    pub fn initial(&self) -> &S {
+        &self.initial
+    }
+
+    /// The declared transition table, in declaration order.
+    pub fn transitions(&self) -> &[Transition<S, E>] {
+        &self.table
+    }
+
+    /// Every successful transition so far, oldest first.
+    pub fn history(&self) -> &[Transition<S, E>] {
+        &self.history
+    }
+
+    /// Looks up where `event` would lead from `from`, without changing anything.
+    pub fn target(&self, from: &S, event: &E) -> Option<&S> {
+        self.index
+            .get(&(from.clone(), event.clone()))
+            .map(|&i| &self.table[i].to)
+    }
+
+    /// Returns `true` if `event` is legal in the current state.
+    pub fn can_fire(&self, event: &E) -> bool {
+        self.target(&self.state, event).is_some()
+    }
+
+    /// The events accepted in the current state, in declaration order.
+    pub fn available_events(&self) -> Vec<&E> {
+        self.table
+            .iter()
+            .filter(|t| t.from == self.state)
+            .map(|t| &t.event)
+            .collect()
+    }
+
+    pub fn on_enter<F>(&mut self, state: S, hook: F)
+    where
+        F: FnMut(&Transition<S, E>) + 'static,
+    {
+        self.on_enter.entry(state).or_default().push(Box::new(hook));
+    }
+
+    pub fn on_exit<F>(&mut self, state: S, hook: F)
+    where
+        F: FnMut(&Transition<S, E>) + 'static,
+    {
+        self.on_exit.entry(state).or_default().push(Box::new(hook));
+    }
+
+    /// Applies `event` to the current state and returns the new state.
+    pub fn fire(&mut self, event: E) -> Result<&S, TransitionError<S, E>> {
+        let to = match self.target(&self.state, &event) {
+            Some(to) => to.clone(),
+            None => {
+                return Err(TransitionError::Illegal {
+                    from: self.state.clone(),
+                    event,
+                })
+            }
+        };
+        let transition = Transition {
+            from: self.state.clone(),
+            event,
+            to,
+        };
+
+        if let Some(hooks) = self.on_exit.get_mut(&transition.from) {
+            hooks.iter_mut().for_each(|hook| hook(&transition));
+        }
+        self.state = transition.to.clone();
+        if let Some(hooks) = self.on_enter.get_mut(&transition.to) {
+            hooks.iter_mut().for_each(|hook| hook(&transition));
+        }
+        self.history.push(transition);
+        Ok(&self.state)
+    }
+
+    /// Overwrites the current state and history without running any hooks.
+    pub(crate) fn replace(&mut self, state: S, history: Vec<Transition<S, E>>) {
+        self.state = state;
+        self.history = history;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Job {
+        Pending,
+        Running,
+        Succeeded,
+        Failed,
+        Retrying,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Event {
+        Start,
+        Finish,
+        Fail,
+        Retry,
+    }
+
+    fn job_machine() -> StateMachine<Job, Event> {
+        StateMachine::new(
+            Job::Pending,
+            [
+                (Job::Pending, Event::Start, Job::Running),
+                (Job::Running, Event::Finish, Job::Succeeded),
+                (Job::Running, Event::Fail, Job::Failed),
+                (Job::Failed, Event::Retry, Job::Retrying),
+                (Job::Retrying, Event::Start, Job::Running),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_fire_follows_table_and_records_history() {
+        let mut job = job_machine();
+        for event in [
+            Event::Start,
+            Event::Fail,
+            Event::Retry,
+            Event::Start,
+            Event::Finish,
+        ] {
+            job.fire(event).unwrap();
+        }
+        assert_eq!(job.state(), &Job::Succeeded);
+        assert_eq!(job.history().len(), 5);
+        assert_eq!(
+            job.history()[2],
+            Transition {
+                from: Job::Failed,
+                event: Event::Retry,
+                to: Job::Retrying
+            }
+        );
+    }
+
+    #[test]
+    fn test_illegal_transition_is_rejected() {
+        let mut job = job_machine();
+        let err = job.fire(Event::Finish).unwrap_err();
+        assert_eq!(
+            err,
+            TransitionError::Illegal {
+                from: Job::Pending,
+                event: Event::Finish
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "illegal transition: Pending does not accept Finish"
+        );
+        assert_eq!(job.state(), &Job::Pending);
+        assert!(job.history().is_empty());
+    }
+
+    #[test]
+    fn test_hooks_run_exit_before_enter() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut job = job_machine();
+        let exit_log = Rc::clone(&log);
+        job.on_exit(Job::Pending, move |t| {
+            exit_log.borrow_mut().push(format!("exit {:?}", t.from))
+        });
+        let enter_log = Rc::clone(&log);
+        job.on_enter(Job::Running, move |t| {
+            enter_log.borrow_mut().push(format!("enter {:?}", t.to))
+        });
+
+        job.fire(Event::Start).unwrap();
+        assert_eq!(*log.borrow(), vec!["exit Pending", "enter Running"]);
+    }
+
+    #[test]
+    fn test_available_events() {
+        let mut job = job_machine();
+        job.fire(Event::Start).unwrap();
+        assert_eq!(job.available_events(), vec![&Event::Finish, &Event::Fail]);
+        assert!(job.can_fire(&Event::Fail));
+        assert!(!job.can_fire(&Event::Retry));
+    }
+}
@@ -0,0 +1,224 @@
+//! `TrafficLight` modelled as a timed [`StateMachine`].
+//!
+//! The light cycles Red → Green → Yellow → Red. Each colour is held for its
+//! [`duration`](TrafficLight::duration), after which a `TimerElapsed` event moves the
+//! machine on. [`TrafficLightController`] turns elapsed wall-clock time into those events.
+
+use crate::export::{RestoreError, Snapshot};
+use crate::state_machine::StateMachine;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrafficLight {
+    Red,
+    Green,
+    Yellow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrafficLightEvent {
+    TimerElapsed,
+}
+
+impl TrafficLight {
+    /// How long the light stays on this colour before the timer elapses.
+    pub fn duration(&self) -> Duration {
+        match self {
+            TrafficLight::Red => Duration::from_secs(30),
+            TrafficLight::Green => Duration::from_secs(25),
+            TrafficLight::Yellow => Duration::from_secs(5),
+        }
+    }
+
+    /// The length of one full Red → Green → Yellow cycle.
+    pub fn cycle_duration() -> Duration {
+        [TrafficLight::Red, TrafficLight::Green, TrafficLight::Yellow]
+            .iter()
+            .map(TrafficLight::duration)
+            .sum()
+    }
+}
+
+pub fn print_light_color(light: TrafficLight) {
+    match light {
+        TrafficLight::Red => println!("Red"),
+        TrafficLight::Green => println!("Green"),
+        TrafficLight::Yellow => println!("Yellow"),
+    }
+}
+
+/// Builds the traffic light state machine, starting on Red.
+pub fn traffic_light_machine() -> StateMachine<TrafficLight, TrafficLightEvent> {
+    use TrafficLight::*;
+    use TrafficLightEvent::TimerElapsed;
+
+    StateMachine::new(
+        Red,
+        [
+            (Red, TimerElapsed, Green),
+            (Green, TimerElapsed, Yellow),
+            (Yellow, TimerElapsed, Red),
+        ],
+    )
+}
+
+/// Drives a traffic light machine from elapsed time.
+///
+/// # Examples
+///
+/// ```
+/// use enum_pattern_match::{TrafficLight, TrafficLightController};
+/// use std::time::Duration;
+///
+/// let mut controller = TrafficLightController::new();
+/// controller.advance(Duration::from_secs(31));
+/// assert_eq!(controller.light(), TrafficLight::Green);
+/// assert_eq!(controller.remaining(), Duration::from_secs(24));
+/// ```
+pub struct TrafficLightController {
+    machine: StateMachine<TrafficLight, TrafficLightEvent>,
+    /// The light `remaining` was counted for, to notice the machine being changed through
+    /// [`machine_mut`](TrafficLightController::machine_mut).
+    timed: TrafficLight,
+    remaining: Duration,
+}
+
+impl TrafficLightController {
+    pub fn new() -> Self {
+        let machine = traffic_light_machine();
+        let timed = *machine.state();
+        TrafficLightController {
+            machine,
+            timed,
+            remaining: timed.duration(),
+        }
+    }
+
+    pub fn light(&self) -> TrafficLight {
+        *self.machine.state()
+    }
+
+    /// Time left before the current colour changes. A light set from outside, by firing
+    /// or restoring the machine directly, starts with its full duration.
+    pub fn remaining(&self) -> Duration {
+        if self.light() == self.timed {
+            self.remaining
+        } else {
+            self.light().duration()
+        }
+    }
+
+    /// Restores the machine from a snapshot, starting the restored light's full duration.
+    pub fn restore(
+        &mut self,
+        snapshot: Snapshot<TrafficLight, TrafficLightEvent>,
+    ) -> Result<(), RestoreError<TrafficLight, TrafficLightEvent>> {
+        self.machine.restore(snapshot)?;
+        self.timed = self.light();
+        self.remaining = self.timed.duration();
+        Ok(())
+    }
+
+    pub fn machine(&self) -> &StateMachine<TrafficLight, TrafficLightEvent> {
+        &self.machine
+    }
+
+    /// Gives access to the underlying machine, e.g. to register hooks.
+    pub fn machine_mut(&mut self) -> &mut StateMachine<TrafficLight, TrafficLightEvent> {
+        &mut self.machine
+    }
+
+    /// Lets `elapsed` time pass, firing a timer event each time a colour runs out.
+    ///
+    /// Returns the number of transitions that happened.
+    pub fn advance(&mut self, mut elapsed: Duration) -> usize {
+        self.remaining = self.remaining();
+        self.timed = self.light();
+        let mut fired = 0;
+        while elapsed >= self.remaining {
+            elapsed -= self.remaining;
+            let next = *self
+                .machine
+                .fire(TrafficLightEvent::TimerElapsed)
+                .expect("every light accepts TimerElapsed");
+            self.timed = next;
+            self.remaining = next.duration();
+            fired += 1;
+        }
+        self.remaining -= elapsed;
+        fired
+    }
+}
+
+impl Default for TrafficLightController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_duration() {
+        assert_eq!(TrafficLight::cycle_duration(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_advance_through_a_full_cycle() {
+        let mut controller = TrafficLightController::new();
+        assert_eq!(controller.advance(Duration::from_secs(29)), 0);
+        assert_eq!(controller.light(), TrafficLight::Red);
+
+        assert_eq!(controller.advance(Duration::from_secs(1)), 1);
+        assert_eq!(controller.light(), TrafficLight::Green);
+
+        assert_eq!(controller.advance(Duration::from_secs(30)), 2);
+        assert_eq!(controller.light(), TrafficLight::Red);
+        assert_eq!(controller.remaining(), TrafficLight::Red.duration());
+
+        let order: Vec<_> = controller
+            .machine()
+            .history()
+            .iter()
+            .map(|t| t.to)
+            .collect();
+        assert_eq!(
+            order,
+            vec![TrafficLight::Green, TrafficLight::Yellow, TrafficLight::Red]
+        );
+    }
+
+    #[test]
+    fn test_advance_spanning_several_cycles() {
+        let mut controller = TrafficLightController::new();
+        let fired =
+            controller.advance(TrafficLight::cycle_duration() * 3 + Duration::from_secs(40));
+        assert_eq!(fired, 10);
+        assert_eq!(controller.light(), TrafficLight::Green);
+        assert_eq!(controller.remaining(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_restore_recomputes_remaining() {
+        let mut source = TrafficLightController::new();
+        source.advance(Duration::from_secs(40));
+        assert_eq!(source.light(), TrafficLight::Green);
+        let snapshot = source.machine().snapshot();
+
+        let mut controller = TrafficLightController::new();
+        controller.advance(Duration::from_secs(20));
+        controller.restore(snapshot.clone()).unwrap();
+        assert_eq!(controller.light(), TrafficLight::Green);
+        assert_eq!(controller.remaining(), TrafficLight::Green.duration());
+
+        let mut direct = TrafficLightController::new();
+        direct.advance(Duration::from_secs(20));
+        direct.machine_mut().restore(snapshot).unwrap();
+        assert_eq!(direct.remaining(), TrafficLight::Green.duration());
+        assert_eq!(direct.advance(Duration::from_secs(25)), 1);
+        assert_eq!(direct.light(), TrafficLight::Yellow);
+    }
+}
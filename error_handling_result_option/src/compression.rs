@@ -0,0 +1,295 @@
+//! Transparent decompression of gzip, zstd and bzip2 files.
+//!
+//! The codec is detected from the file's magic bytes, falling back to the extension when
+//! the header does not match any known format. Each codec is behind a cargo feature of
+//! the same name (all enabled by default); a file in a format whose feature is off is
+//! rejected with [`Error::UnsupportedCompression`].
+
+use crate::error::{Error, Result};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+/// Followed by the block size, `1` to `9`.
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// The compression format of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Detects the format from the first bytes of a file, or from its extension if the
+    /// bytes do not start with a known magic number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use error_handling_result_option::compression::Compression;
+    /// use std::path::Path;
+    ///
+    /// assert_eq!(Compression::detect(&[0x1F, 0x8B, 0x08], Path::new("data")), Compression::Gzip);
+    /// assert_eq!(Compression::detect(b"", Path::new("data.csv.zst")), Compression::Zstd);
+    /// assert_eq!(Compression::detect(b"id,name", Path::new("data.csv")), Compression::None);
+    /// ```
+    pub fn detect(header: &[u8], path: &Path) -> Compression {
+        Self::from_magic(header).unwrap_or_else(|| Self::from_extension(path))
+    }
+
+    pub fn from_magic(header: &[u8]) -> Option<Compression> {
+        if header.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else if header.starts_with(BZIP2_MAGIC)
+            && matches!(header.get(BZIP2_MAGIC.len()), Some(b'1'..=b'9'))
+        {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_extension(path: &Path) -> Compression {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz" | "gzip") => Compression::Gzip,
+            Some("zst" | "zstd") => Compression::Zstd,
+            Some("bz2" | "bzip2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::None => "uncompressed",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Wraps an opened file in the right decoder.
+///
+/// Returns the detected format alongside a reader that yields the decompressed bytes.
+pub(crate) fn decompress(path: &Path, file: File) -> Result<(Compression, Box<dyn BufRead>)> {
+    let mut reader = BufReader::new(file);
+    let header = reader.fill_buf().map_err(|e| Error::from_io(path, e))?;
+    let compression = Compression::detect(header, path);
+
+    let reader: Box<dyn BufRead> = match compression {
+        Compression::None => Box::new(reader),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader)
+                .map_err(|e| Error::from_io(path, e))?,
+        )),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader))),
+        #[allow(unreachable_patterns)]
+        unsupported => {
+            return Err(Error::UnsupportedCompression {
+                path: path.to_path_buf(),
+                compression: unsupported,
+            })
+        }
+    };
+    Ok((compression, reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_file, read_lines};
+    use std::path::PathBuf;
+
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "bzip2"))]
+    const TEXT: &str = "id,name\r\n1,caf\u{e9}\r\n";
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    fn example() -> String {
+        read_file(Path::new(env!("CARGO_MANIFEST_DIR")).join("example.text")).unwrap()
+    }
+
+    /// Writes `bytes` under a name without a compression extension and reads it back.
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "bzip2"))]
+    fn assert_round_trip(name: &str, bytes: Vec<u8>) {
+        let path = std::env::temp_dir().join(format!(
+            "error_handling_compression_{}_{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        assert_eq!(read_file(&path).unwrap(), TEXT.repeat(100));
+        assert_eq!(read_lines(&path).unwrap().count(), 200);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_detect_prefers_magic_over_extension() {
+        let zstd = [0x28, 0xB5, 0x2F, 0xFD, 0x00];
+        assert_eq!(
+            Compression::detect(&zstd, Path::new("a.gz")),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::detect(b"BZh9", Path::new("a")),
+            Compression::Bzip2
+        );
+        assert_eq!(
+            Compression::detect(b"", Path::new("a.bz2")),
+            Compression::Bzip2
+        );
+        for text in [&b"BZh"[..], b"BZh0", b"BZhello"] {
+            assert_eq!(Compression::detect(text, Path::new("a")), Compression::None);
+        }
+        assert_eq!(
+            Compression::detect(b"\x1F", Path::new("a.txt")),
+            Compression::None
+        );
+    }
+
+    #[test]
+    fn test_fixtures_read_and_stream_like_plain_text() {
+        let expected: Vec<_> = example().lines().map(String::from).collect();
+        let codecs = [
+            ("example.text.gz", cfg!(feature = "gzip")),
+            ("example.text.zst", cfg!(feature = "zstd")),
+            ("example.text.bz2", cfg!(feature = "bzip2")),
+        ];
+        for (name, enabled) in codecs {
+            if !enabled {
+                assert!(matches!(
+                    read_file(fixture(name)),
+                    Err(Error::UnsupportedCompression { .. })
+                ));
+                continue;
+            }
+            assert_eq!(read_file(fixture(name)).unwrap(), example(), "{}", name);
+            let lines: Vec<_> = read_lines(fixture(name))
+                .unwrap()
+                .map(|l| l.unwrap().1)
+                .collect();
+            assert_eq!(lines, expected, "{}", name);
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_round_trip() {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(TEXT.repeat(100).as_bytes()).unwrap();
+        assert_round_trip("gzip", encoder.finish().unwrap());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_round_trip() {
+        assert_round_trip(
+            "zstd",
+            zstd::encode_all(TEXT.repeat(100).as_bytes(), 3).unwrap(),
+        );
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_bzip2_round_trip() {
+        use std::io::Write;
+        let mut encoder = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+        encoder.write_all(TEXT.repeat(100).as_bytes()).unwrap();
+        assert_round_trip("bzip2", encoder.finish().unwrap());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_max_size_applies_to_decompressed_bytes() {
+        use crate::reader::ReadOptions;
+        use std::io::Write;
+
+        let path =
+            std::env::temp_dir().join(format!("error_handling_bomb_{}.gz", std::process::id()));
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+        encoder.write_all(&[b'a'; 100_000]).unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let options = ReadOptions::new().max_size(10_000);
+        assert!(matches!(
+            options.read_bytes(&path),
+            Err(Error::TooLarge { limit: 10_000, .. })
+        ));
+        let lines: Vec<_> = options.read_lines(&path).unwrap().collect();
+        assert!(matches!(lines[..], [Err(Error::TooLarge { .. })]));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_read_lines_stops_decompressing_at_the_limit() {
+        use crate::reader::{Lines, ReadOptions};
+        use std::cell::Cell;
+        use std::io::{Read, Write};
+        use std::rc::Rc;
+
+        /// Counts the decompressed bytes handed to the line reader.
+        struct Counting<R>(R, Rc<Cell<u64>>);
+
+        impl<R: Read> Read for Counting<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let read = self.0.read(buf)?;
+                self.1.set(self.1.get() + read as u64);
+                Ok(read)
+            }
+        }
+
+        // 20 MB of a single line compresses to a few kilobytes.
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::fast());
+        for _ in 0..20 {
+            encoder.write_all(&[b'a'; 1 << 20]).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+
+        let counted = Rc::new(Cell::new(0));
+        let decoder = flate2::bufread::MultiGzDecoder::new(std::io::Cursor::new(compressed));
+        let reader = BufReader::with_capacity(64, Counting(decoder, Rc::clone(&counted)));
+        let options = ReadOptions::new().max_size(1024);
+        let mut lines = Lines::new(Path::new("bomb.gz"), Box::new(reader), &options);
+        assert!(matches!(
+            lines.next(),
+            Some(Err(Error::TooLarge { limit: 1024, .. }))
+        ));
+        // The limit, the byte that proves it was passed, and one buffer of read-ahead.
+        assert!(
+            counted.get() <= 1024 + 1 + 64,
+            "read {} bytes",
+            counted.get()
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_corrupt_stream_is_an_io_error() {
+        let path =
+            std::env::temp_dir().join(format!("error_handling_corrupt_{}.gz", std::process::id()));
+        std::fs::write(&path, [0x1F, 0x8B, 0x08, 0x00, 0xFF, 0xFF]).unwrap();
+        assert!(matches!(read_file(&path), Err(Error::Io { .. })));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+//! The crate error type, a `Context` extension trait and a cause-chain reporter.
+//!
+//! Every file error carries the path it happened on, so a caller can tell which file
+//! failed and why. Messages added with [`Context`] wrap the original error rather than
+//! replacing it, and [`Report`] prints the whole chain of causes.
+//!
+//! # Examples
+//!
+//! ```
+//! use error_handling_result_option::{read_file, Context, Error, Report};
+//!
+//! let err = read_file("missing.text").context("loading jokes").unwrap_err();
+//! assert_eq!(err.to_string(), "loading jokes");
+//!
+//! let report = Report::new(&err).to_string();
+//! assert!(report.starts_with("loading jokes\n\nCaused by:\n    0: file not found: missing.text\n"));
+//! ```
+
+use crate::compression::Compression;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::Utf8Error;
+
+/// Errors returned by this crate.
+#[derive(Debug)]
+pub enum Error {
+    NotFound {
+        path: PathBuf,
+        source: io::Error,
+    },
+    PermissionDenied {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The file is not valid UTF-8; `line` is the 1-based line the bad bytes are on.
+    InvalidUtf8 {
+        path: PathBuf,
+        line: Option<usize>,
+        source: Utf8Error,
+    },
+    /// The file has a UTF-16 byte-order mark but is not valid UTF-16.
+    InvalidUtf16 {
+        path: PathBuf,
+        line: Option<usize>,
+    },
+    /// UTF-16 decoding was requested but the file does not start with a byte-order mark.
+    MissingBom {
+        path: PathBuf,
+    },
+    /// The file is compressed with a codec whose cargo feature is disabled.
+    UnsupportedCompression {
+        path: PathBuf,
+        compression: Compression,
+    },
+    /// The file is larger than the `max_size` it was read with.
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A message attached with [`Context`], wrapping the error that caused it.
+    Context {
+        message: String,
+        source: Box<dyn StdError + Send + Sync + 'static>,
+    },
+}
+
+/// A `Result` using the crate [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Classifies an `io::Error` that happened while working on `path`.
+    pub fn from_io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        match source.kind() {
+            io::ErrorKind::NotFound => Error::NotFound { path, source },
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied { path, source },
+            _ => Error::Io { path, source },
+        }
+    }
+
+    /// Whether retrying might help, e.g. because an upstream job is still writing the file.
+    ///
+    /// Missing files, interrupted or timed-out I/O and truncated input are transient;
+    /// permission and encoding problems are not. Context messages are looked through.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::NotFound { .. } => true,
+            Error::Io { source, .. } => matches!(
+                source.kind(),
+                io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            Error::Context { source, .. } => source
+                .downcast_ref::<Error>()
+                .is_some_and(Error::is_transient),
+            _ => false,
+        }
+    }
+
+    /// The path the error happened on, looking through any context messages.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::NotFound { path, .. }
+            | Error::PermissionDenied { path, .. }
+            | Error::InvalidUtf8 { path, .. }
+            | Error::InvalidUtf16 { path, .. }
+            | Error::MissingBom { path }
+            | Error::UnsupportedCompression { path, .. }
+            | Error::TooLarge { path, .. }
+            | Error::Io { path, .. } => Some(path),
+            Error::Context { source, .. } => source.downcast_ref::<Error>()?.path(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound { path, .. } => write!(f, "file not found: {}", path.display()),
+            Error::PermissionDenied { path, .. } => {
+                write!(f, "permission denied: {}", path.display())
+            }
+            Error::InvalidUtf8 { path, line, .. } => {
+                write!(f, "invalid UTF-8 in {}", path.display())?;
+                write_line(f, *line)
+            }
+            Error::InvalidUtf16 { path, line } => {
+                write!(f, "invalid UTF-16 in {}", path.display())?;
+                write_line(f, *line)
+            }
+            Error::MissingBom { path } => {
+                write!(f, "no UTF-16 byte-order mark in {}", path.display())
+            }
+            Error::UnsupportedCompression { path, compression } => write!(
+                f,
+                "{} is {} compressed, but the `{}` feature is disabled",
+                path.display(),
+                compression,
+                compression
+            ),
+            Error::TooLarge { path, size, limit } => write!(
+                f,
+                "{} is too large: {} bytes exceeds the limit of {}",
+                path.display(),
+                size,
+                limit
+            ),
+            Error::Io { path, .. } => write!(f, "I/O error on {}", path.display()),
+            Error::Context { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::NotFound { source, .. }
+            | Error::PermissionDenied { source, .. }
+            | Error::Io { source, .. } => Some(source),
+            Error::InvalidUtf8 { source, .. } => Some(source),
+            Error::Context { source, .. } => Some(source.as_ref()),
+            Error::InvalidUtf16 { .. }
+            | Error::MissingBom { .. }
+            | Error::UnsupportedCompression { .. }
+            | Error::TooLarge { .. } => None,
+        }
+    }
+}
+
+fn write_line(f: &mut fmt::Formatter<'_>, line: Option<usize>) -> fmt::Result {
+    match line {
+        Some(line) => write!(f, " at line {}", line),
+        None => Ok(()),
+    }
+}
+
+/// Attaches a human-readable message to the error of a `Result`.
+pub trait Context<T> {
+    fn context<M: fmt::Display>(self, message: M) -> Result<T>;
+
+    /// Like [`context`](Context::context), but only builds the message on error.
+    fn with_context<M: fmt::Display, F: FnOnce() -> M>(self, message: F) -> Result<T>;
+}
+
+impl<T, E> Context<T> for std::result::Result<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn context<M: fmt::Display>(self, message: M) -> Result<T> {
+        self.map_err(|source| Error::Context {
+            message: message.to_string(),
+            source: Box::new(source),
+        })
+    }
+
+    fn with_context<M: fmt::Display, F: FnOnce() -> M>(self, message: F) -> Result<T> {
+        self.map_err(|source| Error::Context {
+            message: message().to_string(),
+            source: Box::new(source),
+        })
+    }
+}
+
+/// Formats an error followed by its full chain of causes.
+///
+/// ```text
+/// loading jokes
+///
+/// Caused by:
+///     0: file not found: missing.text
+///     1: No such file or directory (os error 2)
+/// ```
+pub struct Report<'a> {
+    error: &'a (dyn StdError + 'static),
+}
+
+impl<'a> Report<'a> {
+    pub fn new(error: &'a (dyn StdError + 'static)) -> Self {
+        Report { error }
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let mut cause = self.error.source();
+        if cause.is_some() {
+            write!(f, "\n\nCaused by:")?;
+        }
+        let mut depth = 0;
+        while let Some(error) = cause {
+            write!(f, "\n    {}: {}", depth, error)?;
+            cause = error.source();
+            depth += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_io_classifies_kind() {
+        let denied = Error::from_io("a.txt", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(denied, Error::PermissionDenied { .. }));
+        assert_eq!(denied.to_string(), "permission denied: a.txt");
+
+        let missing = Error::from_io("a.txt", io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(missing, Error::NotFound { .. }));
+
+        let other = Error::from_io("a.txt", io::Error::from(io::ErrorKind::Interrupted));
+        assert!(matches!(other, Error::Io { .. }));
+        assert_eq!(other.path(), Some(Path::new("a.txt")));
+    }
+
+    #[test]
+    fn test_context_chains_sources() {
+        let result: std::result::Result<(), Error> = Err(Error::from_io(
+            "data.csv",
+            io::Error::new(io::ErrorKind::NotFound, "gone"),
+        ));
+        let err = result
+            .context("reading input")
+            .with_context(|| format!("running job {}", 7))
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "running job 7");
+        assert_eq!(err.path(), Some(Path::new("data.csv")));
+        let chain: Vec<String> =
+            std::iter::successors(Some(&err as &dyn StdError), |&e| e.source())
+                .map(|e| e.to_string())
+                .collect();
+        assert_eq!(
+            chain,
+            vec![
+                "running job 7",
+                "reading input",
+                "file not found: data.csv",
+                "gone"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_prints_cause_chain() {
+        let err = Error::from_io("x", io::Error::other("disk on fire"));
+        assert_eq!(
+            Report::new(&err).to_string(),
+            "I/O error on x\n\nCaused by:\n    0: disk on fire"
+        );
+
+        let plain = io::Error::other("no cause");
+        assert_eq!(Report::new(&plain).to_string(), "no cause");
+    }
+}
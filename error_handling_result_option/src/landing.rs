@@ -0,0 +1,365 @@
+//! Landing-zone helpers: atomic file writes and a polling directory watcher.
+//!
+//! [`write_atomic`] never leaves a half-written file at the target path: readers either
+//! see the old contents or the new ones. [`DirectoryWatcher`] is the other side of the
+//! hand-off, reporting files in a directory once their producer has finished with them.
+//!
+//! Temporary files start with a `.`, and the watcher ignores such hidden files, so a
+//! directory written with `write_atomic` can be watched without extra configuration.
+//!
+//! # Examples
+//!
+//! ```
+//! use error_handling_result_option::landing::{write_atomic, Completion, DirectoryWatcher};
+//!
+//! let dir = std::env::temp_dir().join(format!("landing_doc_{}", std::process::id()));
+//! std::fs::create_dir_all(&dir).unwrap();
+//! let mut watcher = DirectoryWatcher::new(&dir, Completion::DoneMarker);
+//!
+//! write_atomic(dir.join("orders.csv"), b"id,total\n1,9.99\n").unwrap();
+//! assert!(watcher.poll().unwrap().is_empty());
+//!
+//! write_atomic(dir.join("orders.csv.done"), b"").unwrap();
+//! assert_eq!(watcher.poll().unwrap(), vec![dir.join("orders.csv")]);
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::retry::Sleeper;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+const DONE_EXTENSION: &str = "done";
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `bytes` to `path` so that readers never observe a partial file.
+///
+/// The data goes to a hidden temporary file in the same directory, is flushed to disk
+/// with `fsync`, and is then renamed over `path`. On failure the temporary file is
+/// removed and `path` is left untouched.
+pub fn write_atomic(path: impl AsRef<Path>, bytes: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().ok_or_else(|| {
+        Error::from_io(
+            path,
+            io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"),
+        )
+    })?;
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let written = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(Error::from_io(path, e));
+    }
+
+    // Persist the rename itself. Directories cannot be opened for syncing everywhere,
+    // so this is best effort.
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// How the watcher decides that a producer has finished writing a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    /// The file's size and modification time stayed the same for this many polls in a row.
+    StableSize { polls: u32 },
+    /// A `<name>.done` marker file exists next to the file. Markers are never reported.
+    DoneMarker,
+}
+
+/// What was seen of a file that is not complete yet.
+#[derive(Debug, Clone, PartialEq)]
+struct Observation {
+    size: u64,
+    modified: Option<SystemTime>,
+    stable_polls: u32,
+}
+
+/// Polls a directory and reports each file once, when it is complete.
+///
+/// Hidden files, directories and `.done` markers are ignored. A file that is deleted and
+/// then lands again is reported again.
+#[derive(Debug)]
+pub struct DirectoryWatcher {
+    dir: PathBuf,
+    completion: Completion,
+    pending: HashMap<PathBuf, Observation>,
+    reported: HashSet<PathBuf>,
+}
+
+impl DirectoryWatcher {
+    pub fn new(dir: impl AsRef<Path>, completion: Completion) -> Self {
+        DirectoryWatcher {
+            dir: dir.as_ref().to_path_buf(),
+            completion,
+            pending: HashMap::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Scans the directory once and returns the files completed since the last poll,
+    /// sorted by path.
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>> {
+        let dir = &self.dir;
+        let entries = fs::read_dir(dir).map_err(|e| Error::from_io(dir, e))?;
+
+        let mut present = HashSet::new();
+        let mut markers = HashSet::new();
+        let mut files = vec![];
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::from_io(dir, e))?;
+            let path = entry.path();
+            let metadata = match entry.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                // Vanished between listing and stat, or not a regular file.
+                _ => continue,
+            };
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            present.insert(path.clone());
+            if path.extension().is_some_and(|e| e == DONE_EXTENSION) {
+                markers.insert(path);
+            } else {
+                files.push((path, metadata.len(), metadata.modified().ok()));
+            }
+        }
+
+        self.pending.retain(|path, _| present.contains(path));
+        self.reported.retain(|path| present.contains(path));
+
+        let mut completed = vec![];
+        for (path, size, modified) in files {
+            if self.reported.contains(&path) {
+                continue;
+            }
+            let done = match self.completion {
+                Completion::DoneMarker => markers.contains(&marker_for(&path)),
+                Completion::StableSize { polls } => {
+                    let observation = Observation {
+                        size,
+                        modified,
+                        stable_polls: 0,
+                    };
+                    let stable_polls = match self.pending.get(&path) {
+                        Some(seen) if seen.size == size && seen.modified == modified => {
+                            seen.stable_polls + 1
+                        }
+                        _ => 0,
+                    };
+                    self.pending.insert(
+                        path.clone(),
+                        Observation {
+                            stable_polls,
+                            ..observation
+                        },
+                    );
+                    stable_polls >= polls.max(1)
+                }
+            };
+            if done {
+                self.pending.remove(&path);
+                self.reported.insert(path.clone());
+                completed.push(path);
+            }
+        }
+        completed.sort();
+        Ok(completed)
+    }
+
+    /// An endless iterator of completed files, polling every `interval` while idle.
+    pub fn watch<S: Sleeper>(&mut self, interval: Duration, sleeper: S) -> Watch<'_, S> {
+        Watch {
+            watcher: self,
+            interval,
+            sleeper,
+            ready: vec![],
+            polled: false,
+        }
+    }
+}
+
+/// The marker that signals `path` is complete, e.g. `orders.csv.done` for `orders.csv`.
+fn marker_for(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(DONE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Iterator returned by [`DirectoryWatcher::watch`].
+pub struct Watch<'a, S> {
+    watcher: &'a mut DirectoryWatcher,
+    interval: Duration,
+    sleeper: S,
+    /// Completed files not handed out yet, in reverse order.
+    ready: Vec<PathBuf>,
+    polled: bool,
+}
+
+impl<S: Sleeper> Iterator for Watch<'_, S> {
+    type Item = Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(path) = self.ready.pop() {
+                return Some(Ok(path));
+            }
+            if self.polled {
+                self.sleeper.sleep(self.interval);
+            }
+            self.polled = true;
+            match self.watcher.poll() {
+                Ok(mut completed) => {
+                    completed.reverse();
+                    self.ready = completed;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::VirtualSleeper;
+
+    /// Creates an empty, uniquely named directory under the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "error_handling_landing_{}_{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_without_leftovers() {
+        let dir = temp_dir("atomic");
+        let target = dir.join("report.json");
+        write_atomic(&target, b"{\"v\":1}").unwrap();
+        write_atomic(&target, b"{\"v\":2}").unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), b"{\"v\":2}");
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_into_missing_directory() {
+        let dir = temp_dir("missing");
+        let target = dir.join("nope").join("file.txt");
+        let err = write_atomic(&target, b"x").unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert_eq!(err.path(), Some(target.as_path()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_stable_size_waits_for_writer_to_stop() {
+        let dir = temp_dir("stable");
+        let mut watcher = DirectoryWatcher::new(&dir, Completion::StableSize { polls: 2 });
+        let growing = dir.join("events.jsonl");
+
+        fs::write(&growing, "{}\n").unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&growing)
+            .unwrap()
+            .write_all(b"{}\n")
+            .unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+        assert!(watcher.poll().unwrap().is_empty());
+        assert_eq!(watcher.poll().unwrap(), vec![growing.clone()]);
+        assert!(watcher.poll().unwrap().is_empty());
+
+        fs::remove_file(&growing).unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+        fs::write(&growing, "{}\n").unwrap();
+        watcher.poll().unwrap();
+        watcher.poll().unwrap();
+        assert_eq!(watcher.poll().unwrap(), vec![growing]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_done_marker_and_ignored_entries() {
+        let dir = temp_dir("marker");
+        let mut watcher = DirectoryWatcher::new(&dir, Completion::DoneMarker);
+        fs::write(dir.join("b.csv"), "1").unwrap();
+        fs::write(dir.join("a.csv"), "1").unwrap();
+        fs::write(dir.join(".a.csv.123.tmp"), "partial").unwrap();
+        fs::create_dir(dir.join("archive")).unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+
+        fs::write(dir.join("a.csv.done"), "").unwrap();
+        fs::write(dir.join("b.csv.done"), "").unwrap();
+        fs::write(dir.join("orphan.csv.done"), "").unwrap();
+        assert_eq!(names(&watcher.poll().unwrap()), vec!["a.csv", "b.csv"]);
+        assert!(watcher.poll().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_iterator_yields_in_order() {
+        let dir = temp_dir("watch");
+        for name in ["2.csv", "1.csv", "3.csv"] {
+            write_atomic(dir.join(name), b"x").unwrap();
+        }
+        let mut watcher = DirectoryWatcher::new(&dir, Completion::StableSize { polls: 1 });
+        let mut sleeper = VirtualSleeper::new();
+        let found: Vec<_> = watcher
+            .watch(Duration::from_secs(5), &mut sleeper)
+            .take(3)
+            .map(|p| p.unwrap())
+            .collect();
+
+        assert_eq!(names(&found), vec!["1.csv", "2.csv", "3.csv"]);
+        assert_eq!(sleeper.slept(), &[Duration::from_secs(5)]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_poll_missing_directory() {
+        let mut watcher = DirectoryWatcher::new("no_such_landing_zone", Completion::DoneMarker);
+        assert!(matches!(watcher.poll(), Err(Error::NotFound { .. })));
+    }
+}
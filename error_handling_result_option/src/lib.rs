@@ -1,17 +1,55 @@
-use std::fs::File;
-use std::io::{self, Read};
+use std::path::Path;
+
+pub mod compression;
+pub mod error;
+pub mod landing;
+pub mod reader;
+pub mod retry;
+
+pub use compression::Compression;
+pub use error::{Context, Error, Report, Result};
+pub use landing::{write_atomic, Completion, DirectoryWatcher};
+pub use reader::{read_bytes, read_lines, Decoding, ReadOptions};
+pub use retry::{retry, RetryError, RetryPolicy};
 
 /// Reads the contents of a file into a string.
 ///
+/// Errors carry the path that failed: a missing file is [`Error::NotFound`], an unreadable
+/// one [`Error::PermissionDenied`] and a file that is not valid UTF-8 [`Error::InvalidUtf8`].
+/// Gzip, zstd and bzip2 files are decompressed transparently, and a leading UTF-8
+/// byte-order mark is dropped. Use [`ReadOptions`] for other encodings,
+/// a size limit, or to stream the file line by line.
+///
 /// # Examples
 ///
 /// ```
 /// let content = error_handling_result_option::read_file(std::env::current_dir().unwrap().join("example.text").to_str().unwrap());
 /// assert_eq!(content.is_ok(), true);
 /// ```
-pub fn read_file(filename: &str) -> Result<String, io::Error> {
-    let mut file = File::open(filename)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    Ok(content)
+pub fn read_file(filename: impl AsRef<Path>) -> Result<String> {
+    ReadOptions::new().read_to_string(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_file_not_found_carries_path() {
+        let err = read_file("non_existent.text").unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }));
+        assert_eq!(err.path(), Some(Path::new("non_existent.text")));
+    }
+
+    #[test]
+    fn test_read_file_invalid_utf8() {
+        let path = std::env::temp_dir().join("error_handling_invalid_utf8.text");
+        std::fs::write(&path, [b'o', b'k', 0xff, 0xfe]).unwrap();
+        let err = read_file(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        match err {
+            Error::InvalidUtf8 { source, .. } => assert_eq!(source.valid_up_to(), 2),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
 }
@@ -1,10 +1,10 @@
-use error_handling_result_option;
+use error_handling_result_option::{read_file, Context, Report};
 
 fn main() {
     let base_dir = std::env::current_dir().unwrap();
     println!("Current directory: {:?}", base_dir);
-    match error_handling_result_option::read_file(base_dir.join("example.text").to_str().unwrap()) {
+    match read_file(base_dir.join("example.text")).context("could not load the jokes") {
         Ok(content) => println!("{}", content),
-        Err(e) => println!("Error reading file: {}", e),
+        Err(e) => println!("Error reading file: {}", Report::new(&e)),
     }
 }
@@ -0,0 +1,568 @@
+//! Streaming and encoding-aware file reading.
+//!
+//! [`read_file`](crate::read_file) slurps a whole file as strict UTF-8. [`ReadOptions`]
+//! lets the caller pick a [`Decoding`] and a `max_size` guard, and read either the raw
+//! bytes, the whole decoded text, or a lazy iterator of numbered lines. Compressed files
+//! are decompressed on the fly; see [`compression`](crate::compression).
+//!
+//! # Examples
+//!
+//! ```
+//! use error_handling_result_option::reader::{Decoding, ReadOptions};
+//!
+//! let path = std::env::current_dir().unwrap().join("example.text");
+//! let mut lines = ReadOptions::new()
+//!     .decoding(Decoding::Utf8Lossy)
+//!     .max_size(64 * 1024)
+//!     .read_lines(&path)
+//!     .unwrap();
+//! let (number, first) = lines.next().unwrap().unwrap();
+//! assert_eq!((number, first.as_str()), (1, "Why don't scientists trust atoms?"));
+//! ```
+
+use crate::compression::{decompress, Compression};
+use crate::error::{Error, Result};
+use std::fs::File;
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// How file bytes are turned into text.
+///
+/// The UTF-8 modes skip a leading UTF-8 byte-order mark if there is one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Decoding {
+    /// Invalid UTF-8 is an [`Error::InvalidUtf8`].
+    #[default]
+    Utf8Strict,
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    Utf8Lossy,
+    /// UTF-16, little- or big-endian as given by the mandatory byte-order mark.
+    Utf16Bom,
+}
+
+/// Options for reading a file, in the style of `std::fs::OpenOptions`.
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    decoding: Decoding,
+    max_size: Option<u64>,
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Refuses files larger than `bytes` with [`Error::TooLarge`] instead of reading them.
+    ///
+    /// For compressed files the limit applies to the decompressed size.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Reads the whole (decompressed) file as raw bytes.
+    pub fn read_bytes(&self, path: impl AsRef<Path>) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        let mut reader = self.open(path)?;
+        let mut bytes = vec![];
+        match self.max_size {
+            // Read one byte past the limit so growing or compressed files are still caught.
+            Some(limit) => reader.take(limit.saturating_add(1)).read_to_end(&mut bytes),
+            None => reader.read_to_end(&mut bytes),
+        }
+        .map_err(|e| Error::from_io(path, e))?;
+        self.check_size(path, bytes.len() as u64)?;
+        Ok(bytes)
+    }
+
+    /// Reads and decodes the whole file.
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        decode(path, self.decoding, self.read_bytes(path)?)
+    }
+
+    /// Opens the file for lazy, line-by-line decoding.
+    pub fn read_lines(&self, path: impl AsRef<Path>) -> Result<Lines> {
+        let path = path.as_ref();
+        Ok(Lines::new(path, self.open(path)?, self))
+    }
+
+    /// Opens `path` behind the right decompressor.
+    ///
+    /// Uncompressed files over the limit are rejected up front from their metadata.
+    fn open(&self, path: &Path) -> Result<Box<dyn BufRead>> {
+        let file = File::open(path).map_err(|e| Error::from_io(path, e))?;
+        let size = file.metadata().map_err(|e| Error::from_io(path, e))?.len();
+        let (compression, reader) = decompress(path, file)?;
+        if compression == Compression::None {
+            self.check_size(path, size)?;
+        }
+        Ok(reader)
+    }
+
+    fn check_size(&self, path: &Path, size: u64) -> Result<()> {
+        match self.max_size {
+            Some(limit) if size > limit => Err(Error::TooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reads the whole file as raw bytes.
+pub fn read_bytes(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    ReadOptions::new().read_bytes(path)
+}
+
+/// Lazily reads strict UTF-8 lines; see [`ReadOptions::read_lines`] for other decodings.
+pub fn read_lines(path: impl AsRef<Path>) -> Result<Lines> {
+    ReadOptions::new().read_lines(path)
+}
+
+/// Decodes a complete file's bytes.
+pub(crate) fn decode(path: &Path, decoding: Decoding, bytes: Vec<u8>) -> Result<String> {
+    match decoding {
+        Decoding::Utf8Strict => {
+            let bytes = strip_utf8_bom(bytes);
+            String::from_utf8(bytes).map_err(|e| {
+                let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+                let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+                Error::InvalidUtf8 {
+                    path: path.to_path_buf(),
+                    line: Some(line),
+                    source: e.utf8_error(),
+                }
+            })
+        }
+        Decoding::Utf8Lossy => Ok(String::from_utf8_lossy(&strip_utf8_bom(bytes)).into_owned()),
+        Decoding::Utf16Bom => {
+            let big_endian = match bytes.get(..2) {
+                Some(bom) if bom == UTF16_LE_BOM => false,
+                Some(bom) if bom == UTF16_BE_BOM => true,
+                _ => {
+                    return Err(Error::MissingBom {
+                        path: path.to_path_buf(),
+                    })
+                }
+            };
+            let invalid = || Error::InvalidUtf16 {
+                path: path.to_path_buf(),
+                line: None,
+            };
+            let body = &bytes[2..];
+            if !body.len().is_multiple_of(2) {
+                return Err(invalid());
+            }
+            let units: Vec<u16> = body
+                .chunks_exact(2)
+                .map(|pair| to_u16([pair[0], pair[1]], big_endian))
+                .collect();
+            String::from_utf16(&units).map_err(|_| invalid())
+        }
+    }
+}
+
+fn strip_utf8_bom(mut bytes: Vec<u8>) -> Vec<u8> {
+    if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    }
+    bytes
+}
+
+fn to_u16(pair: [u8; 2], big_endian: bool) -> u16 {
+    if big_endian {
+        u16::from_be_bytes(pair)
+    } else {
+        u16::from_le_bytes(pair)
+    }
+}
+
+/// A lazy iterator over the numbered lines of a file.
+///
+/// Yields `(line_number, text)` with 1-based line numbers and the `\n` or `\r\n`
+/// terminator removed. A line that fails to decode yields an error and iteration carries
+/// on with the next line; I/O errors and [`Error::TooLarge`] end the iteration.
+pub struct Lines {
+    path: PathBuf,
+    reader: Box<dyn BufRead>,
+    decoding: Decoding,
+    max_size: Option<u64>,
+    /// `None` until the byte-order mark has been looked at on the first read.
+    big_endian: Option<bool>,
+    line: usize,
+    consumed: u64,
+    done: bool,
+}
+
+impl Lines {
+    pub(crate) fn new(path: &Path, reader: Box<dyn BufRead>, options: &ReadOptions) -> Self {
+        Lines {
+            path: path.to_path_buf(),
+            reader,
+            decoding: options.decoding,
+            max_size: options.max_size,
+            big_endian: None,
+            line: 0,
+            consumed: 0,
+            done: false,
+        }
+    }
+
+    fn read_utf8_line(&mut self) -> Result<Option<String>> {
+        let mut buf = vec![];
+        let read = match self.max_size {
+            // Stop one byte past the limit, so a line with no end in sight is never held
+            // in memory whole.
+            Some(limit) => {
+                let cap = limit.saturating_sub(self.consumed).saturating_add(1);
+                (&mut self.reader).take(cap).read_until(b'\n', &mut buf)
+            }
+            None => self.reader.read_until(b'\n', &mut buf),
+        }
+        .map_err(|e| Error::from_io(&self.path, e))?;
+        self.track(read)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if self.line == 1 {
+            buf = strip_utf8_bom(buf);
+        }
+        if buf.ends_with(b"\n") {
+            buf.pop();
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+        }
+        match self.decoding {
+            Decoding::Utf8Lossy => Ok(Some(String::from_utf8_lossy(&buf).into_owned())),
+            _ => String::from_utf8(buf)
+                .map(Some)
+                .map_err(|e| Error::InvalidUtf8 {
+                    path: self.path.clone(),
+                    line: Some(self.line),
+                    source: e.utf8_error(),
+                }),
+        }
+    }
+
+    fn read_utf16_line(&mut self) -> Result<Option<String>> {
+        let big_endian = match self.big_endian {
+            Some(big_endian) => big_endian,
+            None => {
+                let bom = self.read_pair()?;
+                let big_endian = match bom {
+                    Some(UTF16_LE_BOM) => false,
+                    Some(UTF16_BE_BOM) => true,
+                    _ => {
+                        self.done = true;
+                        return Err(Error::MissingBom {
+                            path: self.path.clone(),
+                        });
+                    }
+                };
+                self.big_endian = Some(big_endian);
+                big_endian
+            }
+        };
+
+        let mut units = vec![];
+        loop {
+            match self.read_pair()? {
+                Some(pair) => {
+                    let unit = to_u16(pair, big_endian);
+                    if unit == u16::from(b'\n') {
+                        break;
+                    }
+                    units.push(unit);
+                }
+                None if units.is_empty() => return Ok(None),
+                None => break,
+            }
+        }
+        if units.last() == Some(&u16::from(b'\r')) {
+            units.pop();
+        }
+        String::from_utf16(&units)
+            .map(Some)
+            .map_err(|_| Error::InvalidUtf16 {
+                path: self.path.clone(),
+                line: Some(self.line),
+            })
+    }
+
+    /// Reads one UTF-16 code unit's worth of bytes, or `None` at a clean end of file.
+    fn read_pair(&mut self) -> Result<Option<[u8; 2]>> {
+        let mut pair = [0u8; 2];
+        let mut filled = 0;
+        while filled < 2 {
+            let read = self
+                .reader
+                .read(&mut pair[filled..])
+                .map_err(|e| Error::from_io(&self.path, e))?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        self.track(filled)?;
+        match filled {
+            0 => Ok(None),
+            2 => Ok(Some(pair)),
+            _ => {
+                self.done = true;
+                Err(Error::InvalidUtf16 {
+                    path: self.path.clone(),
+                    line: Some(self.line),
+                })
+            }
+        }
+    }
+
+    /// Counts bytes against `max_size`, for files that grow while being read.
+    fn track(&mut self, read: usize) -> Result<()> {
+        self.consumed += read as u64;
+        match self.max_size {
+            Some(limit) if self.consumed > limit => {
+                self.done = true;
+                Err(Error::TooLarge {
+                    path: self.path.clone(),
+                    size: self.consumed,
+                    limit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Iterator for Lines {
+    type Item = Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.line += 1;
+        let line = match self.decoding {
+            Decoding::Utf16Bom => self.read_utf16_line(),
+            _ => self.read_utf8_line(),
+        };
+        match line {
+            Ok(Some(text)) => Some(Ok((self.line, text))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                // Decoding errors only spoil one line; anything else spoils the stream.
+                if !matches!(e, Error::InvalidUtf8 { .. } | Error::InvalidUtf16 { .. }) {
+                    self.done = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `bytes` to a uniquely named file in the temp dir and returns its path.
+    fn fixture(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "error_handling_reader_{}_{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = if big_endian {
+            UTF16_BE_BOM.to_vec()
+        } else {
+            UTF16_LE_BOM.to_vec()
+        };
+        for unit in text.encode_utf16() {
+            if big_endian {
+                bytes.extend(unit.to_be_bytes());
+            } else {
+                bytes.extend(unit.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn lines(path: &Path, options: ReadOptions) -> Vec<Result<(usize, String)>> {
+        options.read_lines(path).unwrap().collect()
+    }
+
+    #[test]
+    fn test_read_lines_strips_crlf_and_bom() {
+        let path = fixture("crlf.text", b"\xEF\xBB\xBFfirst\r\nsecond\n\r\nlast");
+        let read: Vec<_> = read_lines(&path).unwrap().map(|l| l.unwrap()).collect();
+        assert_eq!(
+            read,
+            vec![
+                (1, "first".to_string()),
+                (2, "second".to_string()),
+                (3, String::new()),
+                (4, "last".to_string())
+            ]
+        );
+        assert_eq!(
+            ReadOptions::new().read_to_string(&path).unwrap(),
+            "first\r\nsecond\n\r\nlast"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8_strict_and_lossy() {
+        let path = fixture("invalid.text", b"good\nba\xFFd\nalso good\n");
+
+        let strict = lines(&path, ReadOptions::new());
+        assert_eq!(strict[0].as_ref().unwrap().1, "good");
+        match &strict[1] {
+            Err(Error::InvalidUtf8 { line, source, .. }) => {
+                assert_eq!(*line, Some(2));
+                assert_eq!(source.valid_up_to(), 2);
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert_eq!(strict[2].as_ref().unwrap(), &(3, "also good".to_string()));
+
+        let whole = ReadOptions::new().read_to_string(&path).unwrap_err();
+        assert_eq!(
+            whole.to_string(),
+            format!("invalid UTF-8 in {} at line 2", path.display())
+        );
+
+        let lossy = ReadOptions::new().decoding(Decoding::Utf8Lossy);
+        assert_eq!(
+            lossy.read_to_string(&path).unwrap(),
+            "good\nba\u{FFFD}d\nalso good\n"
+        );
+        assert_eq!(lines(&path, lossy)[1].as_ref().unwrap().1, "ba\u{FFFD}d");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let options = ReadOptions::new().decoding(Decoding::Utf16Bom);
+        for (name, big_endian) in [("le.text", false), ("be.text", true)] {
+            let path = fixture(name, &utf16("héllo\r\nwörld 🦀\n", big_endian));
+            assert_eq!(
+                options.read_to_string(&path).unwrap(),
+                "héllo\r\nwörld 🦀\n"
+            );
+            let read: Vec<_> = lines(&path, options.clone())
+                .into_iter()
+                .map(|l| l.unwrap().1)
+                .collect();
+            assert_eq!(read, vec!["héllo", "wörld 🦀"]);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_utf16_errors() {
+        let options = ReadOptions::new().decoding(Decoding::Utf16Bom);
+
+        let no_bom = fixture("nobom.text", b"plain");
+        assert!(matches!(
+            options.read_to_string(&no_bom),
+            Err(Error::MissingBom { .. })
+        ));
+        assert!(matches!(
+            lines(&no_bom, options.clone())[..],
+            [Err(Error::MissingBom { .. })]
+        ));
+
+        let mut lone_surrogate = utf16("ok\n", false);
+        lone_surrogate.extend([0x00, 0xD8, b'\n', 0x00]);
+        let bad = fixture("surrogate.text", &lone_surrogate);
+        assert!(matches!(
+            options.read_to_string(&bad),
+            Err(Error::InvalidUtf16 { .. })
+        ));
+        let read = lines(&bad, options.clone());
+        assert_eq!(read[0].as_ref().unwrap().1, "ok");
+        assert!(matches!(
+            read[1],
+            Err(Error::InvalidUtf16 { line: Some(2), .. })
+        ));
+
+        let odd = fixture("odd.text", &[0xFF, 0xFE, b'a', 0x00, b'b']);
+        assert!(matches!(
+            options.read_to_string(&odd),
+            Err(Error::InvalidUtf16 { .. })
+        ));
+
+        for path in [no_bom, bad, odd] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_max_size_guard() {
+        let path = fixture("big.text", &[b'x'; 100]);
+        let options = ReadOptions::new().max_size(10);
+
+        match options.read_bytes(&path) {
+            Err(Error::TooLarge { size, limit, .. }) => assert_eq!((size, limit), (100, 10)),
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert!(matches!(
+            options.read_lines(&path),
+            Err(Error::TooLarge { .. })
+        ));
+        assert_eq!(read_bytes(&path).unwrap().len(), 100);
+        assert_eq!(
+            ReadOptions::new()
+                .max_size(100)
+                .read_bytes(&path)
+                .unwrap()
+                .len(),
+            100
+        );
+        assert_eq!(
+            ReadOptions::new()
+                .max_size(u64::MAX)
+                .read_bytes(&path)
+                .unwrap()
+                .len(),
+            100
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_max_size_bounds_a_line_without_newline() {
+        // An endless stream, like a file that keeps growing: only the cap stops the read.
+        let reader = Box::new(std::io::BufReader::new(std::io::repeat(b'a')));
+        let options = ReadOptions::new().max_size(1024);
+        let mut lines = Lines::new(Path::new("endless"), reader, &options);
+        match lines.next() {
+            Some(Err(Error::TooLarge { size, limit, .. })) => {
+                assert_eq!((size, limit), (1025, 1024))
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert!(lines.next().is_none());
+    }
+}
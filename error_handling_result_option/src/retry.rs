@@ -0,0 +1,504 @@
+//! Retrying fallible operations with backoff.
+//!
+//! A [`RetryPolicy`] says how long to wait between attempts (constant or exponential
+//! backoff, optionally jittered), when to give up (attempt and elapsed-time limits) and
+//! which errors are worth retrying at all. [`retry`] runs an operation under a policy and,
+//! if it never succeeds, returns a [`RetryError`] holding every failed attempt.
+//!
+//! Waiting goes through a [`Sleeper`], so tests can pass a [`VirtualSleeper`] and run
+//! instantly while still checking the delays that would have been used.
+//!
+//! # Examples
+//!
+//! ```
+//! use error_handling_result_option::retry::{retry_with, RetryPolicy, VirtualSleeper};
+//! use error_handling_result_option::{read_file, Error};
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::exponential(Duration::from_millis(100))
+//!     .max_attempts(4)
+//!     .retry_if(Error::is_transient);
+//! let mut sleeper = VirtualSleeper::new();
+//!
+//! let err = retry_with(&policy, &mut sleeper, || read_file("still_uploading.csv")).unwrap_err();
+//! assert_eq!(err.attempts().len(), 4);
+//! assert_eq!(sleeper.slept(), &[100, 200, 400].map(Duration::from_millis));
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::error::Error as StdError;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// How the delay grows between attempts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backoff {
+    Constant(Duration),
+    /// `initial * factor^(n - 1)` after the n-th failure, capped at `max`.
+    Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// The un-jittered delay after the given 1-based attempt failed.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Backoff::Constant(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => {
+                let scaled = initial.as_secs_f64() * factor.powi(attempt as i32 - 1);
+                // A factor built by hand may be negative or NaN; NaN goes to the cap too.
+                if scaled.is_nan() || scaled >= max.as_secs_f64() {
+                    max
+                } else {
+                    Duration::from_secs_f64(scaled.max(0.0))
+                }
+            }
+        }
+    }
+}
+
+/// When and how often to retry an operation failing with errors of type `E`.
+pub struct RetryPolicy<E> {
+    backoff: Backoff,
+    jitter: f64,
+    seed: Option<u64>,
+    max_attempts: u32,
+    max_elapsed: Option<Duration>,
+    classifier: Box<dyn Fn(&E) -> bool>,
+}
+
+impl<E> RetryPolicy<E> {
+    /// Waits `delay` between attempts. Defaults to 3 attempts, retrying every error.
+    pub fn constant(delay: Duration) -> Self {
+        Self::new(Backoff::Constant(delay))
+    }
+
+    /// Doubles the delay after each failure, starting at `initial` and capped at 30 seconds.
+    pub fn exponential(initial: Duration) -> Self {
+        Self::new(Backoff::Exponential {
+            initial,
+            factor: 2.0,
+            max: Duration::from_secs(30),
+        })
+    }
+
+    pub fn new(backoff: Backoff) -> Self {
+        RetryPolicy {
+            backoff,
+            jitter: 0.0,
+            seed: None,
+            max_attempts: 3,
+            max_elapsed: None,
+            classifier: Box::new(|_| true),
+        }
+    }
+
+    /// Sets the growth factor of exponential backoff; ignored for constant backoff.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `factor` is finite and at least 1.
+    pub fn factor(mut self, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 1.0,
+            "backoff factor must be finite and at least 1, got {}",
+            factor
+        );
+        if let Backoff::Exponential { factor: f, .. } = &mut self.backoff {
+            *f = factor;
+        }
+        self
+    }
+
+    /// Caps the delay of exponential backoff; ignored for constant backoff.
+    pub fn max_delay(mut self, max: Duration) -> Self {
+        if let Backoff::Exponential { max: m, .. } = &mut self.backoff {
+            *m = max;
+        }
+        self
+    }
+
+    /// Shortens each delay by a random fraction of up to `fraction` (clamped to `0..=1`).
+    pub fn jitter(mut self, fraction: f64) -> Self {
+        self.jitter = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Makes the jitter reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The total number of attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Gives up instead of sleeping past `limit` since the first attempt started.
+    pub fn max_elapsed(mut self, limit: Duration) -> Self {
+        self.max_elapsed = Some(limit);
+        self
+    }
+
+    /// Only retries errors for which `classifier` returns `true`.
+    pub fn retry_if<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&E) -> bool + 'static,
+    {
+        self.classifier = Box::new(classifier);
+        self
+    }
+}
+
+/// Something that can wait, and tell how much time has passed.
+pub trait Sleeper {
+    fn sleep(&mut self, duration: Duration);
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl<S: Sleeper + ?Sized> Sleeper for &mut S {
+    fn sleep(&mut self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// Sleeps the current thread.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Records sleeps without waiting; its clock only moves when it "sleeps".
+#[derive(Debug, Clone)]
+pub struct VirtualSleeper {
+    start: Instant,
+    slept: Vec<Duration>,
+}
+
+impl VirtualSleeper {
+    pub fn new() -> Self {
+        VirtualSleeper {
+            start: Instant::now(),
+            slept: vec![],
+        }
+    }
+
+    /// Every delay requested so far.
+    pub fn slept(&self) -> &[Duration] {
+        &self.slept
+    }
+}
+
+impl Default for VirtualSleeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sleeper for VirtualSleeper {
+    fn sleep(&mut self, duration: Duration) {
+        self.slept.push(duration);
+    }
+
+    fn now(&self) -> Instant {
+        self.start + self.slept.iter().sum::<Duration>()
+    }
+}
+
+/// One failed attempt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt<E> {
+    /// 1-based attempt number.
+    pub number: u32,
+    pub error: E,
+    /// Time since the first attempt started, when this one failed.
+    pub elapsed: Duration,
+    /// How long was waited before the next attempt, if there was one.
+    pub delay: Option<Duration>,
+}
+
+/// Why [`retry`] stopped trying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiveUp {
+    /// The classifier said the last error is not worth retrying.
+    NotRetryable,
+    MaxAttempts,
+    /// The next delay would have gone past `max_elapsed`.
+    MaxElapsed,
+}
+
+/// The error returned when every attempt failed, with the full attempt history.
+#[derive(Debug)]
+pub struct RetryError<E> {
+    attempts: Vec<Attempt<E>>,
+    reason: GiveUp,
+}
+
+impl<E> RetryError<E> {
+    pub fn attempts(&self) -> &[Attempt<E>] {
+        &self.attempts
+    }
+
+    pub fn reason(&self) -> GiveUp {
+        self.reason
+    }
+
+    pub fn last_error(&self) -> &E {
+        &self.attempts.last().expect("at least one attempt").error
+    }
+
+    pub fn into_last_error(mut self) -> E {
+        self.attempts.pop().expect("at least one attempt").error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let why = match self.reason {
+            GiveUp::NotRetryable => "error is not retryable",
+            GiveUp::MaxAttempts => "attempt limit reached",
+            GiveUp::MaxElapsed => "time limit reached",
+        };
+        write!(
+            f,
+            "gave up after {} attempt(s) ({}): {}",
+            self.attempts.len(),
+            why,
+            self.last_error()
+        )
+    }
+}
+
+impl<E: StdError + 'static> StdError for RetryError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.last_error())
+    }
+}
+
+/// Runs `op` under `policy`, sleeping the current thread between attempts.
+pub fn retry<T, E, F>(policy: &RetryPolicy<E>, op: F) -> Result<T, RetryError<E>>
+where
+    F: FnMut() -> Result<T, E>,
+{
+    retry_with(policy, &mut ThreadSleeper, op)
+}
+
+/// Runs `op` under `policy`, waiting through the given sleeper.
+pub fn retry_with<T, E, F, S>(
+    policy: &RetryPolicy<E>,
+    sleeper: &mut S,
+    mut op: F,
+) -> Result<T, RetryError<E>>
+where
+    F: FnMut() -> Result<T, E>,
+    S: Sleeper + ?Sized,
+{
+    let start = sleeper.now();
+    let mut rng = policy
+        .seed
+        .unwrap_or_else(|| RandomState::new().build_hasher().finish())
+        | 1;
+    let mut attempts: Vec<Attempt<E>> = vec![];
+
+    for number in 1.. {
+        let error = match op() {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        let elapsed = sleeper.now().duration_since(start);
+        let retryable = (policy.classifier)(&error);
+        attempts.push(Attempt {
+            number,
+            error,
+            elapsed,
+            delay: None,
+        });
+
+        let reason = if !retryable {
+            Some(GiveUp::NotRetryable)
+        } else if number >= policy.max_attempts {
+            Some(GiveUp::MaxAttempts)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(RetryError { attempts, reason });
+        }
+
+        let mut delay = policy.backoff.delay(number);
+        if policy.jitter > 0.0 {
+            delay = delay.mul_f64(1.0 - policy.jitter * next_unit(&mut rng));
+        }
+        if let Some(limit) = policy.max_elapsed {
+            if elapsed + delay > limit {
+                return Err(RetryError {
+                    attempts,
+                    reason: GiveUp::MaxElapsed,
+                });
+            }
+        }
+        sleeper.sleep(delay);
+        if let Some(last) = attempts.last_mut() {
+            last.delay = Some(delay);
+        }
+    }
+    unreachable!("the attempt loop only exits by returning")
+}
+
+/// Xorshift step returning a float in `[0, 1)`.
+fn next_unit(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::io;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_succeeds_after_transient_failures() {
+        let policy = RetryPolicy::constant(ms(50)).max_attempts(5);
+        let mut sleeper = VirtualSleeper::new();
+        let mut calls = 0;
+        let result = retry_with(&policy, &mut sleeper, || {
+            calls += 1;
+            if calls < 3 {
+                Err("not yet")
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(sleeper.slept(), &[ms(50), ms(50)]);
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let policy = RetryPolicy::exponential(ms(100))
+            .factor(3.0)
+            .max_delay(ms(1000))
+            .max_attempts(5);
+        let mut sleeper = VirtualSleeper::new();
+        let err = retry_with(&policy, &mut sleeper, || Err::<(), _>("down")).unwrap_err();
+
+        assert_eq!(err.reason(), GiveUp::MaxAttempts);
+        assert_eq!(sleeper.slept(), &[ms(100), ms(300), ms(900), ms(1000)]);
+        let history: Vec<_> = err.attempts().iter().map(|a| (a.number, a.delay)).collect();
+        assert_eq!(
+            history,
+            vec![
+                (1, Some(ms(100))),
+                (2, Some(ms(300))),
+                (3, Some(ms(900))),
+                (4, Some(ms(1000))),
+                (5, None)
+            ]
+        );
+        assert_eq!(err.attempts()[4].elapsed, ms(2300));
+    }
+
+    #[test]
+    #[should_panic(expected = "backoff factor must be finite and at least 1")]
+    fn test_rejects_shrinking_factor() {
+        let _ = RetryPolicy::<()>::exponential(ms(100)).factor(-2.0);
+    }
+
+    #[test]
+    fn test_delay_survives_hand_built_factors() {
+        for factor in [-2.0, f64::NAN, f64::INFINITY] {
+            let backoff = Backoff::Exponential {
+                initial: ms(100),
+                factor,
+                max: ms(1000),
+            };
+            for attempt in 1..=4 {
+                assert!(backoff.delay(attempt) <= ms(1000));
+            }
+        }
+    }
+
+    #[test]
+    fn test_classifier_stops_on_permanent_errors() {
+        let policy = RetryPolicy::constant(ms(10))
+            .max_attempts(10)
+            .retry_if(Error::is_transient);
+        let mut sleeper = VirtualSleeper::new();
+        let mut errors = vec![
+            Error::from_io("x", io::Error::from(io::ErrorKind::PermissionDenied)),
+            Error::from_io("x", io::Error::from(io::ErrorKind::NotFound)),
+        ];
+        let err = retry_with(&policy, &mut sleeper, || {
+            Err::<(), _>(errors.pop().unwrap())
+        })
+        .unwrap_err();
+
+        assert_eq!(err.reason(), GiveUp::NotRetryable);
+        assert_eq!(err.attempts().len(), 2);
+        assert!(matches!(err.last_error(), Error::PermissionDenied { .. }));
+        assert_eq!(
+            err.to_string(),
+            "gave up after 2 attempt(s) (error is not retryable): permission denied: x"
+        );
+    }
+
+    #[test]
+    fn test_max_elapsed_stops_before_oversleeping() {
+        let policy = RetryPolicy::exponential(ms(100))
+            .max_attempts(100)
+            .max_elapsed(ms(1000));
+        let mut sleeper = VirtualSleeper::new();
+        let err = retry_with(&policy, &mut sleeper, || Err::<(), _>("down")).unwrap_err();
+
+        assert_eq!(err.reason(), GiveUp::MaxElapsed);
+        assert_eq!(sleeper.slept(), &[ms(100), ms(200), ms(400)]);
+        assert_eq!(err.attempts().len(), 4);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_bounds_and_is_seedable() {
+        let policy = || {
+            RetryPolicy::constant(ms(1000))
+                .max_attempts(50)
+                .jitter(0.5)
+                .seed(42)
+        };
+        let run = || {
+            let mut sleeper = VirtualSleeper::new();
+            let _ = retry_with(&policy(), &mut sleeper, || Err::<(), _>("down"));
+            sleeper.slept().to_vec()
+        };
+        let delays = run();
+        assert!(delays.iter().all(|d| *d > ms(500) && *d <= ms(1000)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+        assert_eq!(delays, run());
+    }
+}
@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fibionacci_with_memo::{fibonacci, fibonacci_iterative, fibonacci_matrix, Memo};
+use std::collections::HashMap;
+
+/// The original implementation, threading a `HashMap` through the recursion by hand.
+fn fibonacci_hashmap(n: u32, memo: &mut HashMap<u32, u64>) -> u64 {
+    if n <= 1 {
+        return n as u64;
+    }
+    if let Some(&result) = memo.get(&n) {
+        return result;
+    }
+    let result = fibonacci_hashmap(n - 1, memo) + fibonacci_hashmap(n - 2, memo);
+    memo.insert(n, result);
+    result
+}
+
+fn bench_fibonacci(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci");
+    for n in [20u32, 50, 93] {
+        group.bench_with_input(BenchmarkId::new("recursive_hashmap", n), &n, |b, &n| {
+            b.iter(|| fibonacci_hashmap(black_box(n), &mut HashMap::new()))
+        });
+        group.bench_with_input(BenchmarkId::new("recursive_memo", n), &n, |b, &n| {
+            b.iter(|| fibonacci(black_box(n), &mut Memo::new()))
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", n), &n, |b, &n| {
+            b.iter(|| fibonacci_iterative(black_box(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("matrix", n), &n, |b, &n| {
+            b.iter(|| fibonacci_matrix(black_box(n)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_fibonacci);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+//! Arbitrary-precision Fibonacci numbers.
+
+use num_bigint::BigUint;
+
+/// Calculate the nth Fibonacci number for any `n`, using `BigUint`.
+///
+/// Uses fast doubling, so only O(log n) big-number multiplications are needed:
+///
+/// * `F(2k)   = F(k) * (2 * F(k + 1) - F(k))`
+/// * `F(2k+1) = F(k)^2 + F(k + 1)^2`
+///
+/// # Examples
+///
+/// ```
+/// use fibionacci_with_memo::fibonacci_big;
+///
+/// assert_eq!(fibonacci_big(94).to_string(), "19740274219868223167");
+/// ```
+pub fn fibonacci_big(n: u64) -> BigUint {
+    let mut a = BigUint::ZERO; // F(k)
+    let mut b = BigUint::from(1u32); // F(k + 1)
+
+    // Walk the bits of `n` from the most significant, doubling `k` at each step.
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        let c = &a * (&b * 2u32 - &a);
+        let d = &a * &a + &b * &b;
+        if (n >> bit) & 1 == 1 {
+            b = &c + &d;
+            a = d;
+        } else {
+            a = c;
+            b = d;
+        }
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fibonacci_big_small_values() {
+        let first: Vec<String> = (0..10).map(|n| fibonacci_big(n).to_string()).collect();
+        assert_eq!(
+            first,
+            vec!["0", "1", "1", "2", "3", "5", "8", "13", "21", "34"]
+        );
+    }
+
+    #[test]
+    fn test_fibonacci_big_1000() {
+        assert_eq!(
+            fibonacci_big(1000).to_string(),
+            "43466557686937456435688527675040625802564660517371780402481729089536555417949051890403879840079255169295922593080322634775209689623239873322471161642996440906533187938298969649928516003704476137795166849228875"
+        );
+    }
+
+    #[test]
+    fn test_fibonacci_big_matches_u64_range() {
+        assert_eq!(fibonacci_big(93), BigUint::from(12200160415121876738u64));
+    }
+}
@@ -1,26 +1,220 @@
-use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+pub mod big;
+pub mod memo;
+pub mod modular;
+pub mod store;
+
+pub use big::fibonacci_big;
+pub use memo::{memoize, Cache, Eviction, Memo, SharedMemo, Stats};
+pub use modular::{fibonacci_mod, pisano_period};
 
 /// Calculate the nth Fibonacci number using memoization.
 ///
-/// This function uses a hash map to store previously computed Fibonacci numbers to avoid redundant calculations.
+/// This function uses a [`Cache`] to store previously computed Fibonacci numbers to avoid
+/// redundant calculations: usually a [`Memo`] or a plain `HashMap`, or a
+/// [`store::PersistentMemo`] to keep them across runs. A bounded cache works too, at the cost of recomputing evicted values.
+/// `F(93)` is the largest Fibonacci number that fits in a `u64`; use [`checked_fibonacci`] to
+/// get an error instead of an overflow, or [`fibonacci_big`] for any `n`.
 ///
 /// # Examples
 ///
 /// ```
 /// let n = 10;
-/// use std::collections::HashMap;
-/// use fibionacci_with_memo::fibonacci;
-/// let result = fibionacci_with_memo::fibonacci(n, &mut HashMap::new());
+/// use fibionacci_with_memo::{fibonacci, Memo};
+/// let result = fibionacci_with_memo::fibonacci(n, &mut Memo::new());
 /// assert_eq!(result, 55);
 /// ```
-pub fn fibonacci(n: u32, memo: &mut HashMap<u32, u64>) -> u64 {
+pub fn fibonacci<C: Cache<u32, u64>>(n: u32, memo: &mut C) -> u64 {
+    memoize(memo, |fib, n| {
+        if n <= 1 {
+            n as u64
+        } else {
+            fib(n - 1) + fib(n - 2)
+        }
+    })
+    .call(n)
+}
+
+/// Calculate the nth Fibonacci number with a loop, in O(n) time and O(1) space.
+///
+/// # Panics
+///
+/// Panics if the result overflows a `u64`, i.e. for `n > 93`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(fibionacci_with_memo::fibonacci_iterative(10), 55);
+/// ```
+pub fn fibonacci_iterative(n: u32) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    let (mut a, mut b) = (0u64, 1u64);
+    for _ in 1..n {
+        let next = a.checked_add(b).expect("Fibonacci number overflows u64");
+        (a, b) = (b, next);
+    }
+    b
+}
+
+/// Calculate the nth Fibonacci number by raising `[[1, 1], [1, 0]]` to the nth power, in
+/// O(log n) multiplications.
+///
+/// # Panics
+///
+/// Panics if the result overflows a `u64`, i.e. for `n > 93`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(fibionacci_with_memo::fibonacci_matrix(93), 12200160415121876738);
+/// ```
+pub fn fibonacci_matrix(n: u32) -> u64 {
+    type Matrix = [[u128; 2]; 2];
+
+    fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+        [
+            [
+                a[0][0] * b[0][0] + a[0][1] * b[1][0],
+                a[0][0] * b[0][1] + a[0][1] * b[1][1],
+            ],
+            [
+                a[1][0] * b[0][0] + a[1][1] * b[1][0],
+                a[1][0] * b[0][1] + a[1][1] * b[1][1],
+            ],
+        ]
+    }
+
+    assert!(n <= 93, "Fibonacci({}) overflows u64", n);
+    let mut result: Matrix = [[1, 0], [0, 1]];
+    let mut base: Matrix = [[1, 1], [1, 0]];
+    let mut exponent = n;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = multiply(&result, &base);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = multiply(&base, &base);
+        }
+    }
+    // The top-right entry of the nth power is F(n).
+    result[0][1] as u64
+}
+
+/// Error returned when a Fibonacci number does not fit in the result type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FibonacciError {
+    Overflow { n: u32 },
+}
+
+impl fmt::Display for FibonacciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FibonacciError::Overflow { n } => write!(f, "Fibonacci({}) overflows u64", n),
+        }
+    }
+}
+
+impl Error for FibonacciError {}
+
+/// The largest `n` for which `F(n)` fits in a `u64`.
+const MAX_U64_N: u32 = 93;
+
+/// Calculate the nth Fibonacci number using memoization, returning an error instead of
+/// overflowing.
+///
+/// Values missing from `memo` are filled in with a loop rather than by recursion, so any
+/// `n` returns promptly.
+///
+/// # Examples
+///
+/// ```
+/// use fibionacci_with_memo::{checked_fibonacci, FibonacciError, Memo};
+///
+/// let mut memo = Memo::new();
+/// assert_eq!(checked_fibonacci(93, &mut memo), Ok(12200160415121876738));
+/// assert_eq!(checked_fibonacci(94, &mut memo), Err(FibonacciError::Overflow { n: 94 }));
+/// ```
+pub fn checked_fibonacci<C: Cache<u32, u64>>(n: u32, memo: &mut C) -> Result<u64, FibonacciError> {
+    if n > MAX_U64_N {
+        return Err(FibonacciError::Overflow { n });
+    }
     if n <= 1 {
-        return n as u64;
+        return Ok(n as u64);
+    }
+    if let Some(result) = memo.lookup(&n) {
+        return Ok(result);
     }
-    if let Some(&result) = memo.get(&n) {
-        return result;
+    let (mut a, mut b) = (0u64, 1u64);
+    for i in 2..=n {
+        (a, b) = (b, a + b);
+        memo.store(i, b);
+    }
+    Ok(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_fibonacci_matches_fibonacci() {
+        let mut memo = Memo::new();
+        let mut checked_memo = Memo::new();
+        for n in 0..=93 {
+            assert_eq!(
+                checked_fibonacci(n, &mut checked_memo),
+                Ok(fibonacci(n, &mut memo))
+            );
+        }
+    }
+
+    #[test]
+    fn test_implementations_agree() {
+        let mut memo = Memo::with_capacity(8, Eviction::Lru);
+        for n in 0..=93 {
+            let expected = fibonacci_iterative(n);
+            assert_eq!(fibonacci_matrix(n), expected);
+            assert_eq!(fibonacci(n, &mut memo), expected);
+        }
+        assert!(memo.len() <= 8);
+    }
+
+    #[test]
+    fn test_hash_map_still_works_as_a_memo() {
+        let mut memo: std::collections::HashMap<u32, u64> = std::collections::HashMap::new();
+        assert_eq!(fibonacci(50, &mut memo), 12586269025);
+        assert_eq!(memo.len(), 51);
+        assert_eq!(
+            checked_fibonacci(93, &mut memo),
+            Ok(fibonacci_iterative(93))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows u64")]
+    fn test_iterative_panics_on_overflow() {
+        fibonacci_iterative(94);
+    }
+
+    #[test]
+    fn test_checked_fibonacci_reports_requested_n() {
+        let err = checked_fibonacci(200, &mut Memo::new()).unwrap_err();
+        assert_eq!(err, FibonacciError::Overflow { n: 200 });
+        assert_eq!(err.to_string(), "Fibonacci(200) overflows u64");
+    }
+
+    #[test]
+    fn test_checked_fibonacci_large_n_does_not_recurse() {
+        let mut memo = Memo::new();
+        assert_eq!(
+            checked_fibonacci(1_000_000, &mut memo),
+            Err(FibonacciError::Overflow { n: 1_000_000 })
+        );
+        assert!(memo.is_empty());
     }
-    let result = fibonacci(n - 1, memo) + fibonacci(n - 2, memo);
-    memo.insert(n, result);
-    result
 }
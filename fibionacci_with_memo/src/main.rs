@@ -1,8 +1,27 @@
-use fibionacci_with_memo::fibonacci;
-use std::collections::HashMap;
+use fibionacci_with_memo::{
+    checked_fibonacci, fibonacci, fibonacci_big, fibonacci_matrix, fibonacci_mod, Memo,
+};
 
 fn main() {
-    let mut memo = HashMap::new();
+    let mut memo = Memo::new();
     let n = 40;
     println!("Fibonacci({}) = {}", n, fibonacci(n, &mut memo));
+    println!("Fibonacci({}) = {} (matrix)", n, fibonacci_matrix(n));
+    let stats = memo.stats();
+    println!(
+        "Memo: {} hits, {} misses ({:.0}% hit ratio)",
+        stats.hits,
+        stats.misses,
+        stats.hit_ratio() * 100.0
+    );
+
+    match checked_fibonacci(100, &mut memo) {
+        Ok(result) => println!("Fibonacci(100) = {}", result),
+        Err(e) => println!("Error: {}", e),
+    }
+    println!("Fibonacci(100) = {}", fibonacci_big(100));
+    println!(
+        "Fibonacci(10^18) mod 1_000_000_007 = {}",
+        fibonacci_mod(1_000_000_000_000_000_000, 1_000_000_007)
+    );
 }
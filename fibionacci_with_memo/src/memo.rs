@@ -0,0 +1,454 @@
+//! A generic memoization cache and a combinator for memoizing recursive closures.
+//!
+//! [`Memo`] is a `HashMap`-backed cache that can be bounded, evicting the least recently
+//! used (LRU) or least frequently used (LFU) entry when full, and that counts hits and
+//! misses. [`SharedMemo`] wraps one in `Arc<Mutex<_>>` so threads can share it.
+//!
+//! [`memoize`] removes the need to thread a cache through a recursive function by hand:
+//! the closure receives a `recurse` callback that goes through the cache.
+//!
+//! # Examples
+//!
+//! ```
+//! use fibionacci_with_memo::memo::{memoize, Memo};
+//!
+//! let mut fib = memoize(Memo::new(), |fib, n: u32| {
+//!     if n <= 1 {
+//!         n as u64
+//!     } else {
+//!         fib(n - 1) + fib(n - 2)
+//!     }
+//! });
+//! assert_eq!(fib.call(50), 12586269025);
+//! assert_eq!(fib.cache().stats().misses, 51);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+/// Which entry a full [`Memo`] drops to make room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// Least recently used.
+    Lru,
+    /// Least frequently used, ties broken by least recently used.
+    Lfu,
+}
+
+/// Hit, miss and eviction counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl Stats {
+    /// The fraction of lookups that were hits, or 0 before any lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Something [`memoize`] can cache results in.
+pub trait Cache<K, V> {
+    /// Looks up `key`, counting a hit or a miss.
+    fn lookup(&mut self, key: &K) -> Option<V>;
+
+    fn store(&mut self, key: K, value: V);
+}
+
+impl<K, V, C: Cache<K, V> + ?Sized> Cache<K, V> for &mut C {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        (**self).lookup(key)
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        (**self).store(key, value)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry<V> {
+    value: V,
+    last_used: u64,
+    uses: u64,
+}
+
+/// A memoization cache, optionally bounded with LRU or LFU eviction.
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    /// Entries ordered by eviction rank; the first one is evicted next.
+    order: BTreeMap<(u64, u64), K>,
+    capacity: Option<usize>,
+    eviction: Eviction,
+    clock: u64,
+    stats: Stats,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Memo<K, V> {
+    /// Creates an unbounded cache.
+    pub fn new() -> Self {
+        Memo {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            capacity: None,
+            eviction: Eviction::Lru,
+            clock: 0,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Creates a cache holding at most `capacity` entries.
+    pub fn with_capacity(capacity: usize, eviction: Eviction) -> Self {
+        Memo {
+            capacity: Some(capacity),
+            eviction,
+            ..Self::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Returns `true` if `key` is cached, without counting a lookup or touching recency.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Looks up `key`, counting a hit or a miss and marking the entry as used.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        let old = rank(self.eviction, entry);
+        entry.last_used = self.clock;
+        entry.uses += 1;
+        let new = rank(self.eviction, entry);
+        let value = entry.value.clone();
+        if let Some(k) = self.order.remove(&old) {
+            self.order.insert(new, k);
+        }
+        Some(value)
+    }
+
+    /// Caches `value` under `key`, evicting an entry first if the cache is full.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.clock += 1;
+        if let Some(old) = self.entries.remove(&key) {
+            self.order.remove(&rank(self.eviction, &old));
+        }
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            while self.entries.len() >= capacity {
+                self.evict();
+            }
+        }
+        let entry = Entry {
+            value,
+            last_used: self.clock,
+            uses: 1,
+        };
+        self.order.insert(rank(self.eviction, &entry), key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    /// Returns the cached value for `key`, computing and caching it on a miss.
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f();
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Calls the recursive function `f` for `key`, caching every intermediate result here.
+    ///
+    /// See [`memoize`] for the shape of `f`.
+    pub fn call<F>(&mut self, key: K, f: &F) -> V
+    where
+        F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    {
+        call_cached(self, f, key)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.order.pop_first() {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> for Memo<K, V> {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        self.insert(key, value)
+    }
+}
+
+/// A plain `HashMap` is an unbounded cache that keeps no stats, so a `&mut HashMap`
+/// threaded through by hand can be passed wherever a cache is expected.
+impl<K: Hash + Eq, V: Clone> Cache<K, V> for HashMap<K, V> {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        self.get(key).cloned()
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+}
+
+/// The ordering key of an entry; the entry with the smallest rank is evicted first.
+fn rank<V>(eviction: Eviction, entry: &Entry<V>) -> (u64, u64) {
+    match eviction {
+        Eviction::Lru => (entry.last_used, 0),
+        Eviction::Lfu => (entry.uses, entry.last_used),
+    }
+}
+
+/// A [`Memo`] shared between threads.
+///
+/// Clones are handles to the same cache. The lock is only held for individual lookups
+/// and inserts, never while a value is being computed, so two threads may occasionally
+/// compute the same value.
+#[derive(Debug)]
+pub struct SharedMemo<K, V> {
+    inner: Arc<Mutex<Memo<K, V>>>,
+}
+
+impl<K, V> Clone for SharedMemo<K, V> {
+    fn clone(&self) -> Self {
+        SharedMemo {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> SharedMemo<K, V> {
+    pub fn new(memo: Memo<K, V>) -> Self {
+        SharedMemo {
+            inner: Arc::new(Mutex::new(memo)),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.inner.lock().unwrap().get(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.inner.lock().unwrap().insert(key, value)
+    }
+
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key: K, f: F) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f();
+        self.insert(key, value.clone());
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.inner.lock().unwrap().stats()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> for SharedMemo<K, V> {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        self.insert(key, value)
+    }
+}
+
+/// A recursive function bundled with the cache it memoizes into.
+pub struct Memoized<C, F> {
+    cache: C,
+    f: F,
+}
+
+/// Memoizes a recursive function.
+///
+/// `f` receives a `recurse` callback and the key. Calling `recurse` instead of the
+/// function itself routes every recursive call through `cache`.
+pub fn memoize<K, V, C, F>(cache: C, f: F) -> Memoized<C, F>
+where
+    C: Cache<K, V>,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    Memoized { cache, f }
+}
+
+impl<C, F> Memoized<C, F> {
+    pub fn call<K, V>(&mut self, key: K) -> V
+    where
+        K: Clone,
+        V: Clone,
+        C: Cache<K, V>,
+        F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    {
+        call_cached(&mut self.cache, &self.f, key)
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    pub fn into_cache(self) -> C {
+        self.cache
+    }
+}
+
+fn call_cached<K, V, C, F>(cache: &mut C, f: &F, key: K) -> V
+where
+    K: Clone,
+    V: Clone,
+    C: Cache<K, V>,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    if let Some(value) = cache.lookup(&key) {
+        return value;
+    }
+    let value = f(&mut |k| call_cached(cache, f, k), key.clone());
+    cache.store(key, value.clone());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let mut memo = Memo::with_capacity(2, Eviction::Lru);
+        memo.insert("a", 1);
+        memo.insert("b", 2);
+        assert_eq!(memo.get(&"a"), Some(1));
+        memo.insert("c", 3);
+
+        assert!(memo.contains_key(&"a"));
+        assert!(!memo.contains_key(&"b"));
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_lfu_evicts_least_frequently_used() {
+        let mut memo = Memo::with_capacity(2, Eviction::Lfu);
+        memo.insert("a", 1);
+        memo.insert("b", 2);
+        memo.get(&"a");
+        memo.get(&"a");
+        memo.get(&"b");
+        memo.insert("c", 3);
+        assert!(memo.contains_key(&"a") && !memo.contains_key(&"b"));
+
+        // "c" has been used once, "a" three times.
+        memo.insert("d", 4);
+        assert!(memo.contains_key(&"a") && !memo.contains_key(&"c"));
+    }
+
+    #[test]
+    fn test_stats_and_overwrite() {
+        let mut memo = Memo::new();
+        assert_eq!(memo.get(&1), None);
+        memo.insert(1, "one");
+        memo.insert(1, "uno");
+        assert_eq!(memo.get(&1), Some("uno"));
+        assert_eq!(memo.get_or_insert_with(2, || "two"), "two");
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 1,
+                misses: 2,
+                evictions: 0
+            }
+        );
+        assert!((memo.stats().hit_ratio() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(memo.len(), 2);
+    }
+
+    #[test]
+    fn test_memoize_recursive_closure_with_bounded_cache() {
+        let mut paths = memoize(
+            Memo::with_capacity(64, Eviction::Lru),
+            |paths, (r, c): (u32, u32)| {
+                if r == 0 || c == 0 {
+                    1u64
+                } else {
+                    paths((r - 1, c)) + paths((r, c - 1))
+                }
+            },
+        );
+        assert_eq!(paths.call((16, 16)), 601080390);
+        assert!(paths.cache().len() <= 64);
+    }
+
+    #[test]
+    fn test_shared_memo_across_threads() {
+        let shared = SharedMemo::new(Memo::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let mut square = memoize(shared, |_, n: u64| n * n);
+                    (0..100).map(|n| square.call((n + t) % 100)).sum::<u64>()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 328350);
+        }
+        assert_eq!(shared.len(), 100);
+        let stats = shared.stats();
+        assert_eq!(stats.hits + stats.misses, 400);
+    }
+}
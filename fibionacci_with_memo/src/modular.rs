@@ -0,0 +1,114 @@
+//! Fibonacci numbers modulo `m` and the Pisano period.
+
+/// Calculate `F(n) mod m` in O(log n) using fast doubling.
+///
+/// Intermediate products are taken in `u128`, so any `m` up to `u64::MAX` works.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use fibionacci_with_memo::fibonacci_mod;
+///
+/// assert_eq!(fibonacci_mod(100, 97), 96);
+/// assert_eq!(fibonacci_mod(1_000_000_000_000_000_000, 1_000_000_007), 209783453);
+/// ```
+pub fn fibonacci_mod(n: u64, m: u64) -> u64 {
+    assert!(m != 0, "modulus must be non-zero");
+    let m = m as u128;
+    let mut a = 0u128; // F(k) mod m
+    let mut b = 1 % m; // F(k + 1) mod m
+
+    for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+        let c = a * ((2 * b + m - a) % m) % m;
+        let d = (a * a % m + b * b % m) % m;
+        if (n >> bit) & 1 == 1 {
+            a = d;
+            b = (c + d) % m;
+        } else {
+            a = c;
+            b = d;
+        }
+    }
+    a as u64
+}
+
+/// The Pisano period: the length of the cycle of `F(n) mod m`.
+///
+/// The period is at most `6m`, and is found by stepping the sequence until the pair
+/// `(0, 1)` comes around again.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use fibionacci_with_memo::pisano_period;
+///
+/// assert_eq!(pisano_period(10), 60);
+/// ```
+pub fn pisano_period(m: u64) -> u64 {
+    assert!(m != 0, "modulus must be non-zero");
+    if m == 1 {
+        return 1;
+    }
+    let m = m as u128;
+    let (mut a, mut b) = (0u128, 1u128);
+    let mut period = 0;
+    loop {
+        (a, b) = (b, (a + b) % m);
+        period += 1;
+        if a == 0 && b == 1 {
+            return period;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_big;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_fibonacci_mod_matches_big() {
+        for m in [1u64, 2, 10, 97, 1_000_000_007, u64::MAX] {
+            for n in [0u64, 1, 2, 50, 93, 94, 500, 1000] {
+                let expected = fibonacci_big(n) % BigUint::from(m);
+                assert_eq!(
+                    BigUint::from(fibonacci_mod(n, m)),
+                    expected,
+                    "F({}) mod {}",
+                    n,
+                    m
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_mod_huge_n() {
+        assert_eq!(
+            fibonacci_mod(u64::MAX, 18446744073709551557),
+            18446743708274255395
+        );
+    }
+
+    #[test]
+    fn test_pisano_period_known_values() {
+        let periods: Vec<u64> = [1, 2, 3, 4, 5, 10, 1000].map(pisano_period).to_vec();
+        assert_eq!(periods, vec![1, 3, 8, 6, 20, 60, 1500]);
+    }
+
+    #[test]
+    fn test_pisano_period_reduces_index() {
+        let m = 1000;
+        let n = 123_456_789_012u64;
+        assert_eq!(fibonacci_mod(n, m), fibonacci_mod(n % pisano_period(m), m));
+    }
+}
@@ -0,0 +1,473 @@
+//! Backing stores for memo tables, so cached results can survive a restart.
+//!
+//! A [`Store`] holds the memo table itself. `HashMap` is the in-memory store; [`FileStore`]
+//! keeps the same map in memory and appends every insert to a log file, as JSON lines or
+//! as length-prefixed, checksummed binary records. [`PersistentMemo`] adapts any store to
+//! the [`Cache`] trait, so it works with [`memoize`](crate::memo::memoize) and
+//! [`fibonacci`](crate::fibonacci).
+//!
+//! The log starts with a version header. Opening a log written under a different version
+//! discards it, which is how stale results are invalidated after the computation changes.
+//! A record cut short by a crash fails its framing or checksum and is truncated away the
+//! next time the log is loaded, together with anything after it.
+//!
+//! # Examples
+//!
+//! ```
+//! use fibionacci_with_memo::fibonacci;
+//! use fibionacci_with_memo::store::{FileStore, Format, PersistentMemo};
+//!
+//! let path = std::env::temp_dir().join(format!("fib_doc_{}.memo", std::process::id()));
+//! let mut memo = PersistentMemo::new(FileStore::new(&path, "v1", Format::Json));
+//! assert_eq!(fibonacci(90, &mut memo), 2880067194370816120);
+//!
+//! // A second process picks up where the first left off.
+//! let mut memo = PersistentMemo::new(FileStore::new(&path, "v1", Format::Json));
+//! assert_eq!(fibonacci(90, &mut memo), 2880067194370816120);
+//! assert_eq!(memo.stats().misses, 0);
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+
+use crate::memo::{Cache, Stats};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+const BINARY_MAGIC: &[u8; 4] = b"MEMO";
+
+/// Error returned by a [`Store`].
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Encode(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "memo store I/O error: {}", e),
+            StoreError::Encode(e) => write!(f, "failed to encode memo entry: {}", e),
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Io(e) => Some(e),
+            StoreError::Encode(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> Self {
+        StoreError::Io(e)
+    }
+}
+
+/// A memo table.
+pub trait Store<K, V> {
+    fn get(&mut self, key: &K) -> Result<Option<V>, StoreError>;
+
+    fn insert(&mut self, key: K, value: V) -> Result<(), StoreError>;
+}
+
+impl<K: Hash + Eq, V: Clone> Store<K, V> for HashMap<K, V> {
+    fn get(&mut self, key: &K) -> Result<Option<V>, StoreError> {
+        Ok(HashMap::get(self, key).cloned())
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Result<(), StoreError> {
+        HashMap::insert(self, key, value);
+        Ok(())
+    }
+}
+
+/// How a [`FileStore`] encodes its log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One JSON `[key, value]` array per line, after a `{"version": ...}` header line.
+    Json,
+    /// Records of a little-endian `u32` length, a 64-bit FNV-1a checksum and the bincode
+    /// payload, after a `MEMO` magic and the length-prefixed version.
+    Binary,
+}
+
+/// An append-only log file backing a memo table.
+///
+/// Nothing is read until the first lookup or insert.
+#[derive(Debug)]
+pub struct FileStore<K, V> {
+    path: PathBuf,
+    version: String,
+    format: Format,
+    /// `None` until the log has been loaded.
+    entries: Option<HashMap<K, V>>,
+    file: Option<File>,
+}
+
+impl<K, V> FileStore<K, V>
+where
+    K: Hash + Eq + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+{
+    /// Creates a store backed by `path`, discarding its contents if they were written
+    /// under a different `version`.
+    pub fn new(path: impl AsRef<Path>, version: impl Into<String>, format: Format) -> Self {
+        FileStore {
+            path: path.as_ref().to_path_buf(),
+            version: version.into(),
+            format,
+            entries: None,
+            file: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of entries, loading the log if needed.
+    pub fn len(&mut self) -> Result<usize, StoreError> {
+        Ok(self.load()?.len())
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool, StoreError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Flushes appended records to disk.
+    pub fn sync(&mut self) -> Result<(), StoreError> {
+        if let Some(file) = &self.file {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn load(&mut self) -> Result<&mut HashMap<K, V>, StoreError> {
+        if self.entries.is_none() {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create(true)
+                .open(&self.path)?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+
+            let mut entries = HashMap::new();
+            let valid = match self.format {
+                Format::Json => parse_json(&bytes, &self.version, &mut entries),
+                Format::Binary => parse_binary(&bytes, &self.version, &mut entries),
+            };
+            match valid {
+                Some(len) if len < bytes.len() => file.set_len(len as u64)?,
+                Some(_) => {}
+                None => {
+                    // Missing, torn or stale header: start a fresh log.
+                    file.set_len(0)?;
+                    file.write_all(&self.header())?;
+                }
+            }
+            self.file = Some(file);
+            self.entries = Some(entries);
+        }
+        Ok(self.entries.as_mut().unwrap())
+    }
+
+    fn header(&self) -> Vec<u8> {
+        match self.format {
+            Format::Json => {
+                let mut line = serde_json::json!({ "version": self.version }).to_string();
+                line.push('\n');
+                line.into_bytes()
+            }
+            Format::Binary => {
+                let mut header = BINARY_MAGIC.to_vec();
+                header.extend_from_slice(&(self.version.len() as u32).to_le_bytes());
+                header.extend_from_slice(self.version.as_bytes());
+                header
+            }
+        }
+    }
+
+    fn record(&self, key: &K, value: &V) -> Result<Vec<u8>, StoreError> {
+        match self.format {
+            Format::Json => {
+                let mut line = serde_json::to_vec(&(key, value))
+                    .map_err(|e| StoreError::Encode(e.to_string()))?;
+                line.push(b'\n');
+                Ok(line)
+            }
+            Format::Binary => {
+                let payload = bincode::serialize(&(key, value))
+                    .map_err(|e| StoreError::Encode(e.to_string()))?;
+                let mut record = Vec::with_capacity(12 + payload.len());
+                record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+                record.extend_from_slice(&fnv1a(&payload).to_le_bytes());
+                record.extend_from_slice(&payload);
+                Ok(record)
+            }
+        }
+    }
+}
+
+impl<K, V> Store<K, V> for FileStore<K, V>
+where
+    K: Hash + Eq + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+{
+    fn get(&mut self, key: &K) -> Result<Option<V>, StoreError> {
+        Ok(HashMap::get(self.load()?, key).cloned())
+    }
+
+    fn insert(&mut self, key: K, value: V) -> Result<(), StoreError> {
+        self.load()?;
+        let record = self.record(&key, &value)?;
+        // One write per record, so a crash leaves at most one torn record at the end.
+        self.file.as_mut().unwrap().write_all(&record)?;
+        HashMap::insert(self.entries.as_mut().unwrap(), key, value);
+        Ok(())
+    }
+}
+
+/// Parses a JSON log into `entries`, returning the length of its valid prefix, or `None`
+/// if the header is missing or names another version.
+fn parse_json<K, V>(bytes: &[u8], version: &str, entries: &mut HashMap<K, V>) -> Option<usize>
+where
+    K: Hash + Eq + DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let mut lines = bytes.split_inclusive(|&b| b == b'\n');
+    let header = lines.next().filter(|line| line.ends_with(b"\n"))?;
+    let parsed: serde_json::Value = serde_json::from_slice(header).ok()?;
+    if parsed["version"] != version {
+        return None;
+    }
+    let mut valid = header.len();
+    for line in lines {
+        if !line.ends_with(b"\n") {
+            break;
+        }
+        match serde_json::from_slice::<(K, V)>(line) {
+            Ok((key, value)) => {
+                entries.insert(key, value);
+                valid += line.len();
+            }
+            Err(_) => break,
+        }
+    }
+    Some(valid)
+}
+
+/// Parses a binary log into `entries`; see [`parse_json`].
+fn parse_binary<K, V>(bytes: &[u8], version: &str, entries: &mut HashMap<K, V>) -> Option<usize>
+where
+    K: Hash + Eq + DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let rest = bytes.strip_prefix(BINARY_MAGIC)?;
+    let (len, rest) = split_u32(rest)?;
+    if rest.get(..len)? != version.as_bytes() {
+        return None;
+    }
+    let mut valid = BINARY_MAGIC.len() + 4 + len;
+    let mut rest = &bytes[valid..];
+    while let Some((len, tail)) = split_u32(rest) {
+        let Some((checksum, payload)) = tail.split_first_chunk::<8>() else {
+            break;
+        };
+        let Some(payload) = payload.get(..len) else {
+            break;
+        };
+        if u64::from_le_bytes(*checksum) != fnv1a(payload) {
+            break;
+        }
+        let Ok((key, value)) = bincode::deserialize::<(K, V)>(payload) else {
+            break;
+        };
+        entries.insert(key, value);
+        valid += 12 + len;
+        rest = &bytes[valid..];
+    }
+    Some(valid)
+}
+
+fn split_u32(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    Some((u32::from_le_bytes(*len) as usize, rest))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Adapts a [`Store`] to the [`Cache`] trait, counting hits and misses.
+///
+/// [`Cache`] cannot report errors, so a failing lookup is treated as a miss and a failing
+/// insert only loses persistence; the first error is kept for [`PersistentMemo::take_error`].
+#[derive(Debug)]
+pub struct PersistentMemo<S, K, V> {
+    store: S,
+    stats: Stats,
+    error: Option<StoreError>,
+    _entry: PhantomData<fn(K) -> V>,
+}
+
+impl<S: Store<K, V>, K, V> PersistentMemo<S, K, V> {
+    pub fn new(store: S) -> Self {
+        PersistentMemo {
+            store,
+            stats: Stats::default(),
+            error: None,
+            _entry: PhantomData,
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn backend(&self) -> &S {
+        &self.store
+    }
+
+    pub fn backend_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    pub fn into_backend(self) -> S {
+        self.store
+    }
+
+    /// Returns the first error the store reported since the last call, if any.
+    pub fn take_error(&mut self) -> Option<StoreError> {
+        self.error.take()
+    }
+
+    fn record_error(&mut self, e: StoreError) {
+        self.error.get_or_insert(e);
+    }
+}
+
+impl<S: Store<K, V>, K, V> Cache<K, V> for PersistentMemo<S, K, V> {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        let value = self.store.get(key).unwrap_or_else(|e| {
+            self.record_error(e);
+            None
+        });
+        if value.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        value
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        if let Err(e) = self.store.insert(key, value) {
+            self.record_error(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fib_store_{}_{}", std::process::id(), name))
+    }
+
+    fn reopen(
+        path: &Path,
+        version: &str,
+        format: Format,
+    ) -> PersistentMemo<FileStore<u32, u64>, u32, u64> {
+        PersistentMemo::new(FileStore::new(path, version, format))
+    }
+
+    #[test]
+    fn test_fibonacci_with_each_backend() {
+        let mut memo = PersistentMemo::new(HashMap::new());
+        assert_eq!(fibonacci(80, &mut memo), 23416728348467685);
+        assert_eq!(memo.backend().len(), 81);
+
+        for format in [Format::Json, Format::Binary] {
+            let path = temp_path(&format!("{:?}", format));
+            let _ = std::fs::remove_file(&path);
+            let mut memo = reopen(&path, "v1", format);
+            assert_eq!(fibonacci(80, &mut memo), 23416728348467685);
+            assert!(memo.take_error().is_none());
+
+            let mut memo = reopen(&path, "v1", format);
+            assert_eq!(fibonacci(80, &mut memo), 23416728348467685);
+            assert_eq!(memo.stats().misses, 0);
+            assert_eq!(memo.backend_mut().len().unwrap(), 81);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_is_lazy() {
+        let path = temp_path("lazy");
+        let _ = std::fs::remove_file(&path);
+        let mut store: FileStore<u32, u64> = FileStore::new(&path, "v1", Format::Json);
+        assert!(!path.exists());
+        assert!(store.is_empty().unwrap());
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_version_change_invalidates() {
+        for format in [Format::Json, Format::Binary] {
+            let path = temp_path(&format!("version_{:?}", format));
+            let _ = std::fs::remove_file(&path);
+            fibonacci(30, &mut reopen(&path, "v1", format));
+
+            let mut memo = reopen(&path, "v2", format);
+            assert_eq!(memo.backend_mut().len().unwrap(), 0);
+            fibonacci(10, &mut memo);
+            assert_eq!(reopen(&path, "v2", format).backend_mut().len().unwrap(), 11);
+            assert_eq!(reopen(&path, "v1", format).backend_mut().len().unwrap(), 0);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_torn_tail_is_truncated() {
+        for format in [Format::Json, Format::Binary] {
+            let path = temp_path(&format!("torn_{:?}", format));
+            let _ = std::fs::remove_file(&path);
+            fibonacci(20, &mut reopen(&path, "v1", format));
+            let intact = std::fs::metadata(&path).unwrap().len();
+
+            // Simulate a crash halfway through appending a record.
+            let full = {
+                let store: FileStore<u32, u64> = FileStore::new(&path, "v1", format);
+                store.record(&21, &10946).unwrap()
+            };
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(&full[..full.len() / 2]).unwrap();
+            drop(file);
+
+            let mut memo = reopen(&path, "v1", format);
+            assert_eq!(memo.backend_mut().len().unwrap(), 21);
+            assert_eq!(std::fs::metadata(&path).unwrap().len(), intact);
+            assert_eq!(fibonacci(22, &mut memo), 17711);
+            assert_eq!(reopen(&path, "v1", format).backend_mut().len().unwrap(), 23);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
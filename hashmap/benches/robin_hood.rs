@@ -0,0 +1,96 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use hashmap::RobinHoodMap;
+use std::collections::HashMap;
+
+fn keys(n: usize, prefix: &str) -> Vec<String> {
+    (0..n).map(|i| format!("{}-{:08}", prefix, i)).collect()
+}
+
+fn bench_string_keys(c: &mut Criterion) {
+    for n in [1_000, 100_000] {
+        let present = keys(n, "user");
+        let absent = keys(n, "missing");
+        let std_map: HashMap<String, usize> = present.iter().cloned().zip(0..).collect();
+        let robin_hood: RobinHoodMap<String, usize> = present.iter().cloned().zip(0..).collect();
+
+        let mut group = c.benchmark_group(format!("string_keys/{}", n));
+        group.bench_function(BenchmarkId::new("insert", "std"), |b| {
+            b.iter(|| {
+                let mut map = HashMap::new();
+                for (i, key) in present.iter().enumerate() {
+                    map.insert(key.clone(), i);
+                }
+                map
+            })
+        });
+        group.bench_function(BenchmarkId::new("insert", "robin_hood"), |b| {
+            b.iter(|| {
+                let mut map = RobinHoodMap::new();
+                for (i, key) in present.iter().enumerate() {
+                    map.insert(key.clone(), i);
+                }
+                map
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_hit", "std"), |b| {
+            b.iter(|| {
+                present
+                    .iter()
+                    .filter_map(|k| std_map.get(black_box(k.as_str())))
+                    .count()
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_hit", "robin_hood"), |b| {
+            b.iter(|| {
+                present
+                    .iter()
+                    .filter_map(|k| robin_hood.get(black_box(k.as_str())))
+                    .count()
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_miss", "std"), |b| {
+            b.iter(|| {
+                absent
+                    .iter()
+                    .filter_map(|k| std_map.get(black_box(k.as_str())))
+                    .count()
+            })
+        });
+        group.bench_function(BenchmarkId::new("get_miss", "robin_hood"), |b| {
+            b.iter(|| {
+                absent
+                    .iter()
+                    .filter_map(|k| robin_hood.get(black_box(k.as_str())))
+                    .count()
+            })
+        });
+        group.bench_function(BenchmarkId::new("remove", "std"), |b| {
+            b.iter_batched(
+                || std_map.clone(),
+                |mut map| {
+                    present
+                        .iter()
+                        .filter_map(|k| map.remove(k.as_str()))
+                        .count()
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("remove", "robin_hood"), |b| {
+            b.iter_batched(
+                || robin_hood.clone(),
+                |mut map| {
+                    present
+                        .iter()
+                        .filter_map(|k| map.remove(k.as_str()))
+                        .count()
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_string_keys);
+criterion_main!(benches);
@@ -0,0 +1,298 @@
+//! A consistent hashing ring for spreading keys over a changing set of nodes.
+//!
+//! Each node is hashed onto a 64-bit ring at several points ("virtual nodes"), and a key
+//! belongs to the first node point at or after the key's own hash, wrapping around. Adding
+//! or removing a node only moves the keys between its points and their predecessors,
+//! roughly `1 / nodes` of them, where `hash % nodes` would move almost all. A node's weight
+//! multiplies its number of points, and so its share of the keys.
+//!
+//! Processes sharing a ring must place keys identically, so the ring hashes with
+//! [`StableHasher`] rather than the randomly seeded `RandomState` by default. Keys and
+//! nodes still reach the hasher through their `Hash` impls, whose output std does not
+//! promise to keep across Rust versions, and integers (including `usize` length
+//! prefixes) are written as native-endian bytes of the platform's width. So placement is
+//! only guaranteed to agree between processes running the same binary on the same target.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::ConsistentHashRing;
+//!
+//! let mut ring = ConsistentHashRing::new();
+//! ring.add_node("worker-a");
+//! ring.add_node("worker-b");
+//! ring.add_weighted_node("worker-c", 2);
+//!
+//! let owner = *ring.node_for("customer:42").unwrap();
+//! let replicas = ring.nodes_for("customer:42", 2);
+//! assert_eq!(*replicas[0], owner);
+//! assert_ne!(replicas[0], replicas[1]);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::ops::Bound;
+
+const DEFAULT_VIRTUAL_NODES: usize = 160;
+
+/// FNV-1a with a 64-bit mixing step on output. Unlike `RandomState` it is unseeded, so
+/// the same bytes always hash to the same value; see the module docs for why the same
+/// *key* may still hash differently under another build or target.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        // The murmur3 finalizer: FNV alone leaves similar inputs clustered on the ring.
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51afd7ed558ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+        h ^ (h >> 33)
+    }
+}
+
+/// A consistent hashing ring of nodes of type `N`.
+#[derive(Debug, Clone)]
+pub struct ConsistentHashRing<N, S = BuildHasherDefault<StableHasher>> {
+    ring: BTreeMap<u64, N>,
+    weights: HashMap<N, u32>,
+    virtual_nodes: usize,
+    hash_builder: S,
+}
+
+impl<N: Hash + Eq + Clone> ConsistentHashRing<N> {
+    /// Creates an empty ring with 160 points per unit of node weight.
+    pub fn new() -> Self {
+        Self::with_virtual_nodes(DEFAULT_VIRTUAL_NODES)
+    }
+
+    /// Creates an empty ring with `virtual_nodes` points per unit of node weight.
+    ///
+    /// More points even out the load at the cost of a bigger ring.
+    pub fn with_virtual_nodes(virtual_nodes: usize) -> Self {
+        Self::with_hasher(virtual_nodes, BuildHasherDefault::default())
+    }
+}
+
+impl<N: Hash + Eq + Clone> Default for ConsistentHashRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Hash + Eq + Clone, S: BuildHasher> ConsistentHashRing<N, S> {
+    /// # Panics
+    ///
+    /// Panics if `virtual_nodes` is zero.
+    pub fn with_hasher(virtual_nodes: usize, hash_builder: S) -> Self {
+        assert!(virtual_nodes > 0, "need at least one virtual node per node");
+        ConsistentHashRing {
+            ring: BTreeMap::new(),
+            weights: HashMap::new(),
+            virtual_nodes,
+            hash_builder,
+        }
+    }
+
+    /// The number of nodes.
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.weights.contains_key(node)
+    }
+
+    pub fn weight(&self, node: &N) -> Option<u32> {
+        self.weights.get(node).copied()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (&N, u32)> {
+        self.weights.iter().map(|(node, &weight)| (node, weight))
+    }
+
+    /// Adds `node` with weight 1. Returns `false` if it was already on the ring.
+    pub fn add_node(&mut self, node: N) -> bool {
+        self.add_weighted_node(node, 1)
+    }
+
+    /// Adds `node`, or changes its weight if it is already on the ring. Returns `false` in
+    /// the latter case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero.
+    pub fn add_weighted_node(&mut self, node: N, weight: u32) -> bool {
+        assert!(weight > 0, "node weight must be positive");
+        let added = !self.remove_node(&node);
+        for replica in 0..self.virtual_nodes * weight as usize {
+            let point = self.hash_builder.hash_one((&node, replica as u64));
+            // A full 64-bit collision is vanishingly rare; the earlier node keeps the point.
+            self.ring.entry(point).or_insert_with(|| node.clone());
+        }
+        self.weights.insert(node, weight);
+        added
+    }
+
+    /// Removes `node` and its points. Returns `false` if it was not on the ring.
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        if self.weights.remove(node).is_none() {
+            return false;
+        }
+        self.ring.retain(|_, owner| owner != node);
+        true
+    }
+
+    /// The node that owns `key`, or `None` if the ring is empty.
+    pub fn node_for<K: Hash + ?Sized>(&self, key: &K) -> Option<&N> {
+        self.walk(key).next()
+    }
+
+    /// Up to `replicas` distinct nodes for `key`: its owner first, then the next distinct
+    /// nodes clockwise around the ring.
+    pub fn nodes_for<K: Hash + ?Sized>(&self, key: &K, replicas: usize) -> Vec<&N> {
+        let mut nodes: Vec<&N> = Vec::with_capacity(replicas.min(self.len()));
+        for node in self.walk(key) {
+            if nodes.len() == replicas.min(self.len()) {
+                break;
+            }
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    /// Node points clockwise from the hash of `key`, wrapping around once.
+    fn walk<K: Hash + ?Sized>(&self, key: &K) -> impl Iterator<Item = &N> {
+        let hash = self.hash_builder.hash_one(key);
+        self.ring
+            .range((Bound::Included(hash), Bound::Unbounded))
+            .chain(self.ring.range(..hash))
+            .map(|(_, node)| node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: usize = 50_000;
+
+    fn ring(nodes: usize) -> ConsistentHashRing<String> {
+        let mut ring = ConsistentHashRing::new();
+        for i in 0..nodes {
+            ring.add_node(format!("node-{}", i));
+        }
+        ring
+    }
+
+    fn owners(ring: &ConsistentHashRing<String>) -> Vec<String> {
+        (0..KEYS)
+            .map(|k| ring.node_for(&format!("key-{}", k)).unwrap().clone())
+            .collect()
+    }
+
+    fn loads(ring: &ConsistentHashRing<String>) -> HashMap<String, usize> {
+        let mut loads = HashMap::new();
+        for owner in owners(ring) {
+            *loads.entry(owner).or_insert(0) += 1;
+        }
+        loads
+    }
+
+    #[test]
+    fn test_adding_a_node_moves_only_its_share() {
+        let mut ring = ring(10);
+        let before = owners(&ring);
+        ring.add_node("node-10".to_string());
+        let after = owners(&ring);
+
+        let moved: Vec<_> = (0..KEYS).filter(|&k| before[k] != after[k]).collect();
+        // Ideally 1/11 of the keys, all of them to the new node.
+        let fraction = moved.len() as f64 / KEYS as f64;
+        assert!((0.06..0.13).contains(&fraction), "moved {}", fraction);
+        assert!(moved.iter().all(|&k| after[k] == "node-10"));
+
+        // Modulo hashing would have moved about 10/11 of them.
+        let modulo_moved = (0..KEYS as u64).filter(|k| k % 10 != k % 11).count();
+        assert!(modulo_moved as f64 / KEYS as f64 > 0.85);
+    }
+
+    #[test]
+    fn test_removing_a_node_moves_only_its_keys() {
+        let mut ring = ring(10);
+        let before = owners(&ring);
+        assert!(ring.remove_node(&"node-3".to_string()));
+        assert!(!ring.remove_node(&"node-3".to_string()));
+        let after = owners(&ring);
+        for k in 0..KEYS {
+            if before[k] != "node-3" {
+                assert_eq!(before[k], after[k]);
+            }
+        }
+        assert!(!after.iter().any(|owner| owner == "node-3"));
+
+        ring.add_node("node-3".to_string());
+        assert_eq!(owners(&ring), before);
+    }
+
+    #[test]
+    fn test_load_balance() {
+        let loads = loads(&ring(10));
+        let mean = KEYS as f64 / 10.0;
+        let max = *loads.values().max().unwrap() as f64;
+        let min = *loads.values().min().unwrap() as f64;
+        assert!(max / mean < 1.2, "max load {} vs mean {}", max, mean);
+        assert!(min / mean > 0.8, "min load {} vs mean {}", min, mean);
+    }
+
+    #[test]
+    fn test_weights_scale_share() {
+        let mut ring = ring(4);
+        ring.add_weighted_node("node-big".to_string(), 3);
+        assert_eq!(ring.weight(&"node-big".to_string()), Some(3));
+        let share = loads(&ring)["node-big"] as f64 / KEYS as f64;
+        // 3 of 7 units of weight.
+        assert!((share - 3.0 / 7.0).abs() < 0.05, "share {}", share);
+
+        assert!(!ring.add_weighted_node("node-big".to_string(), 1));
+        let share = loads(&ring)["node-big"] as f64 / KEYS as f64;
+        assert!((share - 0.2).abs() < 0.05, "share {}", share);
+    }
+
+    #[test]
+    fn test_nodes_for_returns_distinct_replicas() {
+        let ring = ring(5);
+        for k in 0..100 {
+            let key = format!("key-{}", k);
+            let replicas = ring.nodes_for(&key, 3);
+            assert_eq!(replicas.len(), 3);
+            assert_eq!(replicas[0], ring.node_for(&key).unwrap());
+            assert!(replicas[0] != replicas[1] && replicas[1] != replicas[2]);
+            assert!(replicas[0] != replicas[2]);
+        }
+        assert_eq!(ring.nodes_for("key", 10).len(), 5);
+        assert!(ConsistentHashRing::<String>::new()
+            .node_for("key")
+            .is_none());
+    }
+}
@@ -0,0 +1,659 @@
+//! Group records by key and aggregate each group, optionally across threads.
+//!
+//! A [`GroupBy`] is built from a key closure plus a list of named aggregations, each with
+//! a closure that pulls a [`Value`] out of a record. Applying it to an iterator of records
+//! returns one [`AggRow`] per distinct key.
+//!
+//! Nulls are skipped by sum, min, max, mean and distinct-count, as in SQL; so are values
+//! those aggregations cannot combine, such as strings in a sum. Count, first, last and
+//! collect see every record.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::group_by::{GroupBy, Value};
+//!
+//! let sales = [("north", 3), ("south", 5), ("north", 4)];
+//! let groups = GroupBy::new(|s: &(&str, i64)| s.0)
+//!     .count("orders")
+//!     .sum("units", |s| s.1)
+//!     .max("largest", |s| s.1)
+//!     .apply(sales);
+//!
+//! assert_eq!(groups["north"]["orders"], Value::Int(2));
+//! assert_eq!(groups["north"]["units"], Value::Int(7));
+//! assert_eq!(groups["south"]["largest"], Value::Int(5));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
+use std::thread;
+
+/// A single aggregated or extracted value.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Orders numbers with numbers, strings with strings and booleans with booleans.
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (a, b) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        }
+    }
+
+    fn add(&self, other: &Value) -> Option<Value> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(
+                a.checked_add(*b)
+                    .map(Value::Int)
+                    .unwrap_or(Value::Float(*a as f64 + *b as f64)),
+            ),
+            (a, b) => Some(Value::Float(a.as_f64()? + b.as_f64()?)),
+        }
+    }
+}
+
+/// Floats compare and hash by their bits, so `Value` can be used in sets; note that this
+/// makes `NaN` equal to itself and `0.0` different from `-0.0`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Bool(b) => b.hash(state),
+            Value::Int(i) => i.hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::Str(s) => s.hash(state),
+            Value::List(l) => l.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Int(i as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(i: u32) -> Self {
+        Value::Int(i as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+/// An aggregation function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Agg {
+    /// The number of records, as an `Int`.
+    Count,
+    /// An `Int` while every value is an integer and the total fits, otherwise a `Float`.
+    Sum,
+    Min,
+    Max,
+    /// A `Float`, or `Null` if the group has no numbers.
+    Mean,
+    /// The number of distinct non-null values, as an `Int`.
+    DistinctCount,
+    First,
+    Last,
+    /// Every value in record order, as a `List`.
+    Collect,
+}
+
+/// The partial result of one aggregation over part of a group.
+#[derive(Debug, Clone)]
+enum State {
+    Count(u64),
+    Sum(Option<Value>),
+    Min(Option<Value>),
+    Max(Option<Value>),
+    Mean { sum: f64, count: u64 },
+    Distinct(HashSet<Value>),
+    First(Option<Value>),
+    Last(Option<Value>),
+    Collect(Vec<Value>),
+}
+
+impl State {
+    fn new(agg: Agg) -> State {
+        match agg {
+            Agg::Count => State::Count(0),
+            Agg::Sum => State::Sum(None),
+            Agg::Min => State::Min(None),
+            Agg::Max => State::Max(None),
+            Agg::Mean => State::Mean { sum: 0.0, count: 0 },
+            Agg::DistinctCount => State::Distinct(HashSet::new()),
+            Agg::First => State::First(None),
+            Agg::Last => State::Last(None),
+            Agg::Collect => State::Collect(Vec::new()),
+        }
+    }
+
+    fn update(&mut self, value: Value) {
+        match self {
+            State::Count(n) => *n += 1,
+            State::First(first) => {
+                first.get_or_insert(value);
+            }
+            State::Last(last) => *last = Some(value),
+            State::Collect(values) => values.push(value),
+            _ if value.is_null() => {}
+            State::Sum(sum) => match sum {
+                None if value.as_f64().is_some() => *sum = Some(value),
+                None => {}
+                Some(total) => {
+                    if let Some(next) = total.add(&value) {
+                        *total = next;
+                    }
+                }
+            },
+            State::Min(min) => keep_if(min, value, Ordering::Less),
+            State::Max(max) => keep_if(max, value, Ordering::Greater),
+            State::Mean { sum, count } => {
+                if let Some(x) = value.as_f64() {
+                    *sum += x;
+                    *count += 1;
+                }
+            }
+            State::Distinct(seen) => {
+                seen.insert(value);
+            }
+        }
+    }
+
+    /// Folds in the state of a later part of the same group.
+    fn merge(&mut self, later: State) {
+        match (self, later) {
+            (State::Count(a), State::Count(b)) => *a += b,
+            (State::Sum(a), State::Sum(Some(b))) => match a {
+                None => *a = Some(b),
+                Some(total) => {
+                    if let Some(next) = total.add(&b) {
+                        *total = next;
+                    }
+                }
+            },
+            (State::Min(a), State::Min(Some(b))) => keep_if(a, b, Ordering::Less),
+            (State::Max(a), State::Max(Some(b))) => keep_if(a, b, Ordering::Greater),
+            (State::Mean { sum, count }, State::Mean { sum: s, count: c }) => {
+                *sum += s;
+                *count += c;
+            }
+            (State::Distinct(a), State::Distinct(b)) => a.extend(b),
+            (State::First(a), State::First(b)) if a.is_none() => *a = b,
+            (State::Last(a), State::Last(b)) if b.is_some() => *a = b,
+            (State::Collect(a), State::Collect(b)) => a.extend(b),
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            State::Count(n) => Value::Int(n as i64),
+            State::Sum(v) | State::Min(v) | State::Max(v) | State::First(v) | State::Last(v) => {
+                v.unwrap_or(Value::Null)
+            }
+            State::Mean { count: 0, .. } => Value::Null,
+            State::Mean { sum, count } => Value::Float(sum / count as f64),
+            State::Distinct(seen) => Value::Int(seen.len() as i64),
+            State::Collect(values) => Value::List(values),
+        }
+    }
+}
+
+/// Replaces `current` with `value` if `value` compares as `wanted` against it.
+fn keep_if(current: &mut Option<Value>, value: Value, wanted: Ordering) {
+    match current {
+        None => *current = Some(value),
+        Some(existing) => {
+            if value.compare(existing) == Some(wanted) {
+                *existing = value;
+            }
+        }
+    }
+}
+
+/// The aggregated values of one group, in the order the aggregations were added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggRow {
+    columns: Vec<(String, Value)>,
+}
+
+impl AggRow {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.columns.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.columns.iter().map(|(n, v)| (n.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+impl Index<&str> for AggRow {
+    type Output = Value;
+
+    fn index(&self, name: &str) -> &Value {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no aggregation named {:?}", name))
+    }
+}
+
+type Extractor<R> = Box<dyn Fn(&R) -> Value + Send + Sync>;
+
+struct Aggregation<R> {
+    name: String,
+    agg: Agg,
+    extract: Extractor<R>,
+}
+
+/// A group-by query over records of type `R`, keyed by `K`.
+pub struct GroupBy<R, K> {
+    key: Box<dyn Fn(&R) -> K + Send + Sync>,
+    aggregations: Vec<Aggregation<R>>,
+    threads: usize,
+}
+
+impl<R, K: Hash + Eq> GroupBy<R, K> {
+    pub fn new<F>(key: F) -> Self
+    where
+        F: Fn(&R) -> K + Send + Sync + 'static,
+    {
+        GroupBy {
+            key: Box::new(key),
+            aggregations: Vec::new(),
+            threads: 1,
+        }
+    }
+
+    /// Adds an aggregation named `name` over the values `extract` returns.
+    pub fn agg<F, T>(mut self, name: &str, agg: Agg, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.aggregations.push(Aggregation {
+            name: name.to_string(),
+            agg,
+            extract: Box::new(move |r| extract(r).into()),
+        });
+        self
+    }
+
+    pub fn count(self, name: &str) -> Self {
+        self.agg(name, Agg::Count, |_| Value::Null)
+    }
+
+    pub fn sum<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Sum, extract)
+    }
+
+    pub fn min<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Min, extract)
+    }
+
+    pub fn max<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Max, extract)
+    }
+
+    pub fn mean<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Mean, extract)
+    }
+
+    pub fn distinct_count<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::DistinctCount, extract)
+    }
+
+    pub fn first<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::First, extract)
+    }
+
+    pub fn last<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Last, extract)
+    }
+
+    pub fn collect<F, T>(self, name: &str, extract: F) -> Self
+    where
+        F: Fn(&R) -> T + Send + Sync + 'static,
+        T: Into<Value>,
+    {
+        self.agg(name, Agg::Collect, extract)
+    }
+
+    /// Splits the records into `threads` contiguous chunks aggregated in parallel.
+    ///
+    /// The per-thread maps are merged in chunk order, so first, last and collect give the
+    /// same results as a sequential run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn parallel(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "need at least one thread");
+        self.threads = threads;
+        self
+    }
+
+    /// Runs the query, returning one row per distinct key.
+    pub fn apply<I>(&self, records: I) -> HashMap<K, AggRow>
+    where
+        I: IntoIterator<Item = R>,
+        R: Send + Sync,
+        K: Send,
+    {
+        let partial = if self.threads == 1 {
+            self.partial(records)
+        } else {
+            let records: Vec<R> = records.into_iter().collect();
+            let chunk = records.len().div_ceil(self.threads).max(1);
+            let partials: Vec<_> = thread::scope(|s| {
+                let handles: Vec<_> = records
+                    .chunks(chunk)
+                    .map(|chunk| s.spawn(move || self.partial(chunk)))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            merge(partials)
+        };
+        partial
+            .into_iter()
+            .map(|(key, states)| (key, self.finish(states)))
+            .collect()
+    }
+
+    fn partial<T: std::borrow::Borrow<R>>(
+        &self,
+        records: impl IntoIterator<Item = T>,
+    ) -> HashMap<K, Vec<State>> {
+        let mut groups: HashMap<K, Vec<State>> = HashMap::new();
+        for record in records {
+            let record = record.borrow();
+            let states = groups.entry((self.key)(record)).or_insert_with(|| {
+                self.aggregations
+                    .iter()
+                    .map(|a| State::new(a.agg))
+                    .collect()
+            });
+            for (state, aggregation) in states.iter_mut().zip(&self.aggregations) {
+                state.update((aggregation.extract)(record));
+            }
+        }
+        groups
+    }
+
+    fn finish(&self, states: Vec<State>) -> AggRow {
+        AggRow {
+            columns: self
+                .aggregations
+                .iter()
+                .zip(states)
+                .map(|(a, state)| (a.name.clone(), state.finish()))
+                .collect(),
+        }
+    }
+}
+
+/// Merges per-chunk partial maps, given in chunk order.
+fn merge<K: Hash + Eq>(partials: Vec<HashMap<K, Vec<State>>>) -> HashMap<K, Vec<State>> {
+    let mut partials = partials.into_iter();
+    let mut merged = partials.next().unwrap_or_default();
+    for partial in partials {
+        for (key, states) in partial {
+            match merged.get_mut(&key) {
+                Some(existing) => {
+                    for (state, later) in existing.iter_mut().zip(states) {
+                        state.merge(later);
+                    }
+                }
+                None => {
+                    merged.insert(key, states);
+                }
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[derive(Debug, Clone)]
+    struct Sale {
+        region: String,
+        product: String,
+        customer: String,
+        units: i64,
+        price: Option<f64>,
+    }
+
+    fn sales() -> Vec<Sale> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/sales.csv");
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        reader
+            .records()
+            .map(|r| {
+                let r = r.unwrap();
+                Sale {
+                    region: r[0].to_string(),
+                    product: r[1].to_string(),
+                    customer: r[2].to_string(),
+                    units: r[3].parse().unwrap(),
+                    price: r[4].parse().ok(),
+                }
+            })
+            .collect()
+    }
+
+    fn by_region() -> GroupBy<Sale, String> {
+        GroupBy::new(|s: &Sale| s.region.clone())
+            .count("orders")
+            .sum("units", |s| s.units)
+            .min("min_price", |s| s.price)
+            .max("max_units", |s| s.units)
+            .mean("mean_price", |s| s.price)
+            .distinct_count("customers", |s| s.customer.clone())
+            .first("first_product", |s| s.product.clone())
+            .last("last_product", |s| s.product.clone())
+            .collect("units_seen", |s| s.units)
+    }
+
+    #[test]
+    fn test_aggregates_sales_fixture() {
+        let groups = by_region().apply(sales());
+        assert_eq!(groups.len(), 3);
+
+        let north = &groups["north"];
+        assert_eq!(north["orders"], Value::Int(4));
+        assert_eq!(north["units"], Value::Int(22));
+        assert_eq!(north["min_price"], Value::Float(2.5));
+        assert_eq!(north["max_units"], Value::Int(10));
+        assert_eq!(north["mean_price"], Value::Float(5.0));
+        assert_eq!(north["customers"], Value::Int(3));
+        assert_eq!(north["first_product"], Value::from("apple"));
+        assert_eq!(north["last_product"], Value::from("cherry"));
+        assert_eq!(
+            north["units_seen"],
+            Value::List(vec![3.into(), 10.into(), 5.into(), 4.into()])
+        );
+        let names: Vec<_> = north.iter().map(|(name, _)| name).collect();
+        assert_eq!(names[..3], ["orders", "units", "min_price"]);
+
+        // West only has rows without a price.
+        let west = &groups["west"];
+        assert_eq!(west["min_price"], Value::Null);
+        assert_eq!(west["mean_price"], Value::Null);
+        assert_eq!(west["orders"], Value::Int(2));
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let records: Vec<_> = (0..50).flat_map(|_| sales()).collect();
+        let sequential = by_region().apply(records.clone());
+        for threads in [2, 3, 8, 64] {
+            assert_eq!(
+                by_region().parallel(threads).apply(records.clone()),
+                sequential
+            );
+        }
+        assert_eq!(sequential["north"]["orders"], Value::Int(200));
+    }
+
+    #[test]
+    fn test_composite_keys_and_mixed_values() {
+        let groups = GroupBy::new(|s: &Sale| (s.region.clone(), s.product.clone()))
+            .sum("revenue", |s| s.price.map(|p| p * s.units as f64))
+            .apply(sales());
+        assert_eq!(
+            groups[&("north".to_string(), "apple".to_string())]["revenue"],
+            Value::Float(3.0 * 2.5 + 5.0 * 2.5)
+        );
+
+        let mixed = GroupBy::new(|_: &Value| ())
+            .sum("sum", Value::clone)
+            .max("max", Value::clone)
+            .apply([
+                Value::Int(i64::MAX),
+                Value::Null,
+                Value::from("x"),
+                Value::Int(1),
+            ]);
+        assert_eq!(mixed[&()]["sum"], Value::Float(i64::MAX as f64 + 1.0));
+        assert_eq!(mixed[&()]["max"], Value::Int(i64::MAX));
+    }
+}
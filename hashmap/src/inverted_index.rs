@@ -0,0 +1,550 @@
+//! Full-text search over short documents such as log messages.
+//!
+//! An [`InvertedIndex`] maps every term to the sorted list of documents containing it
+//! (its postings) and remembers where in each document the term occurs. Boolean queries
+//! are answered with set intersection, union and difference over the postings, phrase
+//! queries by checking that the terms occur at consecutive positions, and results can be
+//! ranked by TF-IDF.
+//!
+//! Query strings support `AND`, `OR`, `NOT`, parentheses and `"quoted phrases"`; terms
+//! next to each other are implicitly ANDed, and `AND` binds tighter than `OR`. Terms go
+//! through the index's [`Tokenizer`], so they match however the documents were
+//! normalised. A term that tokenizes to nothing, such as a stop word, matches nothing.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::InvertedIndex;
+//!
+//! let mut index = InvertedIndex::default();
+//! let full = index.add("ERROR disk full on /dev/sda1");
+//! let timeout = index.add("WARN request timeout after 30s");
+//! let slow = index.add("ERROR disk slow, request queued");
+//!
+//! assert_eq!(index.search("error AND disk").unwrap(), [full, slow]);
+//! assert_eq!(index.search("disk NOT full").unwrap(), [slow]);
+//! assert_eq!(index.search("\"request timeout\" OR full").unwrap(), [full, timeout]);
+//! ```
+
+use crate::tokenizer::{Tokenizer, TokenizerExt, Whitespace};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// Identifies a document in an [`InvertedIndex`]. Ids are assigned in insertion order.
+pub type DocId = u32;
+
+/// A parsed search query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Documents containing the term. If the term tokenizes into several tokens, the
+    /// document must contain all of them.
+    Term(String),
+    /// Documents containing the tokens of the text at consecutive positions.
+    Phrase(String),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+impl Query {
+    pub fn term(text: &str) -> Query {
+        Query::Term(text.to_string())
+    }
+
+    pub fn phrase(text: &str) -> Query {
+        Query::Phrase(text.to_string())
+    }
+
+    /// Parses a query string; see the [module documentation](self) for the syntax.
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(token) => Err(ParseError::Unexpected(token.to_string())),
+        }
+    }
+
+    /// The term and phrase texts that count towards a document's score, i.e. those not
+    /// under a `NOT`.
+    fn positive_texts<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Query::Term(text) | Query::Phrase(text) => out.push(text),
+            Query::And(queries) | Query::Or(queries) => {
+                queries.iter().for_each(|q| q.positive_texts(out))
+            }
+            Query::Not(_) => {}
+        }
+    }
+}
+
+/// Error returned when a query string is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    UnclosedQuote,
+    UnclosedParen,
+    /// A token that does not fit the grammar where it appears.
+    Unexpected(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "expected a search term"),
+            ParseError::UnclosedQuote => write!(f, "unclosed quote in query"),
+            ParseError::UnclosedParen => write!(f, "unclosed parenthesis in query"),
+            ParseError::Unexpected(token) => write!(f, "unexpected {:?} in query", token),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Lexeme {
+    Word(String),
+    Quoted(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lexeme::Word(w) => write!(f, "{}", w),
+            Lexeme::Quoted(q) => write!(f, "\"{}\"", q),
+            Lexeme::And => write!(f, "AND"),
+            Lexeme::Or => write!(f, "OR"),
+            Lexeme::Not => write!(f, "NOT"),
+            Lexeme::Open => write!(f, "("),
+            Lexeme::Close => write!(f, ")"),
+        }
+    }
+}
+
+fn lex(input: &str) -> Result<Vec<Lexeme>, ParseError> {
+    let mut lexemes = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                lexemes.push(if c == '(' {
+                    Lexeme::Open
+                } else {
+                    Lexeme::Close
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err(ParseError::UnclosedQuote),
+                    }
+                }
+                lexemes.push(Lexeme::Quoted(phrase));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                lexemes.push(match word.as_str() {
+                    "AND" => Lexeme::And,
+                    "OR" => Lexeme::Or,
+                    "NOT" => Lexeme::Not,
+                    _ => Lexeme::Word(word),
+                });
+            }
+        }
+    }
+    Ok(lexemes)
+}
+
+/// A recursive-descent parser over the grammar
+///
+/// ```text
+/// or    := and ("OR" and)*
+/// and   := unary ("AND"? unary)*
+/// unary := "NOT" unary | "(" or ")" | quoted | word
+/// ```
+struct Parser {
+    tokens: Vec<Lexeme>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Lexeme> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Query, ParseError> {
+        let mut queries = vec![self.and()?];
+        while self.peek() == Some(&Lexeme::Or) {
+            self.pos += 1;
+            queries.push(self.and()?);
+        }
+        Ok(flatten(queries, Query::Or))
+    }
+
+    fn and(&mut self) -> Result<Query, ParseError> {
+        let mut queries = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(Lexeme::And) => self.pos += 1,
+                Some(Lexeme::Or | Lexeme::Close) | None => break,
+                Some(_) => {}
+            }
+            queries.push(self.unary()?);
+        }
+        Ok(flatten(queries, Query::And))
+    }
+
+    fn unary(&mut self) -> Result<Query, ParseError> {
+        let token = self.peek().cloned().ok_or(ParseError::Empty)?;
+        self.pos += 1;
+        match token {
+            Lexeme::Not => Ok(Query::Not(Box::new(self.unary()?))),
+            Lexeme::Open => {
+                let query = self.or()?;
+                if self.peek() != Some(&Lexeme::Close) {
+                    return Err(ParseError::UnclosedParen);
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            Lexeme::Quoted(phrase) => Ok(Query::Phrase(phrase)),
+            Lexeme::Word(word) => Ok(Query::Term(word)),
+            other => Err(ParseError::Unexpected(other.to_string())),
+        }
+    }
+}
+
+fn flatten(mut queries: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if queries.len() == 1 {
+        queries.pop().unwrap()
+    } else {
+        combine(queries)
+    }
+}
+
+/// An inverted index over a growing collection of documents.
+pub struct InvertedIndex {
+    tokenizer: Box<dyn Tokenizer + Send + Sync>,
+    postings: HashMap<String, Vec<DocId>>,
+    /// For each term, the positions it occurs at in each document containing it.
+    positions: HashMap<String, HashMap<DocId, Vec<usize>>>,
+    /// The number of tokens in each document.
+    lengths: Vec<usize>,
+}
+
+impl InvertedIndex {
+    pub fn new(tokenizer: impl Tokenizer + Send + Sync + 'static) -> Self {
+        InvertedIndex {
+            tokenizer: Box::new(tokenizer),
+            postings: HashMap::new(),
+            positions: HashMap::new(),
+            lengths: Vec::new(),
+        }
+    }
+
+    /// The number of documents.
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// Indexes `text` as a new document and returns its id.
+    pub fn add(&mut self, text: &str) -> DocId {
+        let id = self.lengths.len() as DocId;
+        let tokens = self.tokenizer.tokenize(text);
+        self.lengths.push(tokens.len());
+        for token in tokens {
+            let positions = self.positions.entry(token.text.clone()).or_default();
+            let doc_positions = positions.entry(id).or_default();
+            if doc_positions.is_empty() {
+                self.postings.entry(token.text).or_default().push(id);
+            }
+            doc_positions.push(token.position);
+        }
+        id
+    }
+
+    /// The documents containing `term`, which must already be tokenized, in id order.
+    pub fn postings(&self, term: &str) -> &[DocId] {
+        self.postings.get(term).map_or(&[], Vec::as_slice)
+    }
+
+    /// How many times `term` occurs in document `doc`.
+    pub fn term_frequency(&self, term: &str, doc: DocId) -> usize {
+        self.positions
+            .get(term)
+            .and_then(|docs| docs.get(&doc))
+            .map_or(0, Vec::len)
+    }
+
+    /// The number of documents containing `term`.
+    pub fn document_frequency(&self, term: &str) -> usize {
+        self.postings(term).len()
+    }
+
+    /// Parses and evaluates `query`, returning the matching documents in id order.
+    pub fn search(&self, query: &str) -> Result<Vec<DocId>, ParseError> {
+        Ok(self.evaluate(&Query::parse(query)?))
+    }
+
+    /// Parses and evaluates `query`, returning the matching documents by descending
+    /// TF-IDF score; see [`InvertedIndex::rank`].
+    pub fn search_ranked(&self, query: &str) -> Result<Vec<(DocId, f64)>, ParseError> {
+        Ok(self.rank(&Query::parse(query)?))
+    }
+
+    /// Returns the documents matching `query` in id order.
+    pub fn evaluate(&self, query: &Query) -> Vec<DocId> {
+        let mut docs: Vec<_> = self.matching(query).into_iter().collect();
+        docs.sort_unstable();
+        docs
+    }
+
+    /// Returns the documents matching `query`, best first.
+    ///
+    /// A document scores the sum, over the tokens of every term and phrase not under a
+    /// `NOT`, of the token's frequency in the document divided by the document's length,
+    /// times the token's inverse document frequency `ln((1 + N) / (1 + df)) + 1`. Ties
+    /// are broken by document id.
+    pub fn rank(&self, query: &Query) -> Vec<(DocId, f64)> {
+        let mut texts = Vec::new();
+        query.positive_texts(&mut texts);
+        let terms: Vec<String> = texts
+            .into_iter()
+            .flat_map(|text| self.tokenizer.tokenize(text))
+            .map(|token| token.text)
+            .collect();
+
+        let mut ranked: Vec<_> = self
+            .matching(query)
+            .into_iter()
+            .map(|doc| {
+                let score: f64 = terms.iter().map(|term| self.tf_idf(term, doc)).sum();
+                (doc, score)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    fn tf_idf(&self, term: &str, doc: DocId) -> f64 {
+        let length = self.lengths[doc as usize];
+        if length == 0 {
+            return 0.0;
+        }
+        let tf = self.term_frequency(term, doc) as f64 / length as f64;
+        let n = self.len() as f64;
+        let df = self.document_frequency(term) as f64;
+        tf * (((1.0 + n) / (1.0 + df)).ln() + 1.0)
+    }
+
+    fn matching(&self, query: &Query) -> HashSet<DocId> {
+        match query {
+            Query::Term(text) => self.all_tokens(text),
+            Query::Phrase(text) => self.phrase(text),
+            Query::And(queries) => {
+                let mut sets: Vec<_> = queries.iter().map(|q| self.matching(q)).collect();
+                // Intersect starting from the smallest set.
+                sets.sort_by_key(HashSet::len);
+                let mut sets = sets.into_iter();
+                let first = sets.next().unwrap_or_default();
+                sets.fold(first, |acc, set| &acc & &set)
+            }
+            Query::Or(queries) => queries.iter().flat_map(|q| self.matching(q)).collect(),
+            Query::Not(query) => {
+                let excluded = self.matching(query);
+                (0..self.len() as DocId)
+                    .filter(|doc| !excluded.contains(doc))
+                    .collect()
+            }
+        }
+    }
+
+    /// Documents containing every token of `text`.
+    fn all_tokens(&self, text: &str) -> HashSet<DocId> {
+        let tokens = self.tokenizer.tokenize(text);
+        let mut postings: Vec<&[DocId]> = tokens.iter().map(|t| self.postings(&t.text)).collect();
+        postings.sort_by_key(|p| p.len());
+        let Some((first, rest)) = postings.split_first() else {
+            return HashSet::new();
+        };
+        let mut docs: HashSet<DocId> = first.iter().copied().collect();
+        for p in rest {
+            let set: HashSet<DocId> = p.iter().copied().collect();
+            docs.retain(|doc| set.contains(doc));
+        }
+        docs
+    }
+
+    /// Documents where the tokens of `text` occur at the same relative positions as in
+    /// `text` itself.
+    fn phrase(&self, text: &str) -> HashSet<DocId> {
+        let tokens = self.tokenizer.tokenize(text);
+        let Some(start) = tokens.first().map(|t| t.position) else {
+            return HashSet::new();
+        };
+        let mut docs = self.all_tokens(text);
+        docs.retain(|&doc| {
+            let positions = |term: &str| &self.positions[term][&doc];
+            positions(&tokens[0].text).iter().any(|&p| {
+                tokens[1..].iter().all(|t| {
+                    let want = p + (t.position - start);
+                    positions(&t.text).contains(&want)
+                })
+            })
+        });
+        docs
+    }
+}
+
+/// Lowercases and splits on whitespace.
+impl Default for InvertedIndex {
+    fn default() -> Self {
+        InvertedIndex::new(Whitespace.lowercase())
+    }
+}
+
+impl fmt::Debug for InvertedIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InvertedIndex")
+            .field("documents", &self.len())
+            .field("terms", &self.postings.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGS: [&str; 6] = [
+        "ERROR disk full on node-1",
+        "WARN disk usage at 91 percent on node-2",
+        "ERROR connection timeout talking to node-3",
+        "INFO node-1 is back and the disk is healthy",
+        "ERROR disk full disk full disk full",
+        "WARN request timeout but the retry succeeded",
+    ];
+
+    fn index() -> InvertedIndex {
+        let mut index = InvertedIndex::new(
+            Whitespace
+                .lowercase()
+                .stop_words(["the", "is", "at", "on", "to", "and"]),
+        );
+        for line in LOGS {
+            index.add(line);
+        }
+        index
+    }
+
+    #[test]
+    fn test_postings_and_frequencies() {
+        let index = index();
+        assert_eq!(index.postings("disk"), [0, 1, 3, 4]);
+        assert_eq!(index.postings("the"), [] as [DocId; 0]);
+        assert_eq!(index.term_frequency("disk", 4), 3);
+        assert_eq!(index.document_frequency("timeout"), 2);
+    }
+
+    #[test]
+    fn test_boolean_queries() {
+        let index = index();
+        assert_eq!(index.search("error disk").unwrap(), [0, 4]);
+        assert_eq!(index.search("error AND disk").unwrap(), [0, 4]);
+        assert_eq!(index.search("timeout OR full").unwrap(), [0, 2, 4, 5]);
+        assert_eq!(index.search("disk NOT error").unwrap(), [1, 3]);
+        assert_eq!(
+            index.search("(warn OR info) AND NOT timeout").unwrap(),
+            [1, 3]
+        );
+        assert_eq!(
+            index.search("warn OR error AND timeout").unwrap(),
+            [1, 2, 5]
+        );
+        assert_eq!(index.search("NOT the").unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_phrase_queries_use_positions() {
+        let index = index();
+        assert_eq!(index.search("\"disk full\"").unwrap(), [0, 4]);
+        assert!(index.search("\"full disk\"").unwrap().contains(&4));
+        assert_eq!(index.search("\"full node-1\"").unwrap(), [] as [DocId; 0]);
+        // "on" is a stop word but still occupies a position.
+        assert_eq!(index.search("\"full on node-1\"").unwrap(), [0]);
+        assert_eq!(index.search("\"disk is healthy\"").unwrap(), [3]);
+    }
+
+    #[test]
+    fn test_ngram_index_matches_partial_words() {
+        let mut index = InvertedIndex::new(Whitespace.lowercase().ngrams(3));
+        index.add("Connection refused");
+        index.add("connected");
+        index.add("disconnect requested");
+        assert_eq!(index.search("connect").unwrap(), [0, 1, 2]);
+        assert_eq!(index.search("nnecti").unwrap(), [0]);
+        assert_eq!(index.search("\"connect requ\"").unwrap(), [2]);
+    }
+
+    #[test]
+    fn test_tf_idf_ranking() {
+        let index = index();
+        let ranked = index.search_ranked("disk full").unwrap();
+        let docs: Vec<_> = ranked.iter().map(|(doc, _)| *doc).collect();
+        // Document 4 repeats both terms; document 0 is longer.
+        assert_eq!(docs, [4, 0]);
+        assert!(ranked[0].1 > ranked[1].1);
+
+        let ranked = index.search_ranked("(disk OR timeout) NOT full").unwrap();
+        assert_eq!(ranked.len(), 4);
+        assert!(ranked.iter().all(|(doc, _)| ![0, 4].contains(doc)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Query::parse(""), Err(ParseError::Empty));
+        assert_eq!(Query::parse("(disk"), Err(ParseError::UnclosedParen));
+        assert_eq!(Query::parse("\"disk full"), Err(ParseError::UnclosedQuote));
+        assert_eq!(
+            Query::parse("disk )"),
+            Err(ParseError::Unexpected(")".to_string()))
+        );
+        assert_eq!(
+            Query::parse("disk AND OR full"),
+            Err(ParseError::Unexpected("OR".to_string()))
+        );
+        assert_eq!(
+            Query::parse("a b OR NOT \"c d\""),
+            Ok(Query::Or(vec![
+                Query::And(vec![Query::term("a"), Query::term("b")]),
+                Query::Not(Box::new(Query::phrase("c d"))),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,766 @@
+//! Hash joins between a build side held in a `HashMap` and a streamed probe side.
+//!
+//! The probe side is the left input of the join and the build side the right one, which
+//! matches the usual shape of joining a reference table (built) to an event stream
+//! (probed): [`JoinKind::Left`] keeps every event, and [`JoinKind::Semi`] and
+//! [`JoinKind::Anti`] filter events by whether the reference table has their key. The
+//! hash table is built from the build side, unless both sides know their exact length and
+//! the probe side is the shorter one.
+//!
+//! [`hash_join`] keeps the whole build side in memory and yields rows lazily as the probe
+//! side is consumed. [`hash_join_with_spill`] partitions both sides to disk when the build
+//! side outgrows a memory budget and joins the partitions one at a time (a grace hash
+//! join).
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::join::{hash_join, JoinKind, JoinRow};
+//!
+//! let countries = [("KE", "Kenya"), ("UG", "Uganda")];
+//! let events = [(1, "KE"), (2, "TZ"), (3, "KE")];
+//!
+//! let rows: Vec<_> = hash_join(countries, events, |c| c.0, |e| e.1, JoinKind::Left).collect();
+//! assert_eq!(rows.len(), 3);
+//! assert_eq!(rows[1], JoinRow { probe: Some((2, "TZ")), build: None });
+//! assert_eq!(rows[2].build, Some(("KE", "Kenya")));
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::hash_map::{self, DefaultHasher};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Which rows a join produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Matching pairs only.
+    Inner,
+    /// Matching pairs, plus probe rows without a match.
+    Left,
+    /// Matching pairs, plus build rows without a match.
+    Right,
+    /// Matching pairs, plus unmatched rows from both sides.
+    Full,
+    /// Probe rows that have at least one match, once each.
+    Semi,
+    /// Probe rows that have no match.
+    Anti,
+}
+
+impl JoinKind {
+    fn keeps_unmatched_build(self) -> bool {
+        matches!(self, JoinKind::Right | JoinKind::Full)
+    }
+
+    /// The same join with the sides exchanged. Semi and anti joins have no such
+    /// counterpart, and are handled by [`Probing::filter_table`] instead.
+    fn swapped(self) -> Self {
+        match self {
+            JoinKind::Left => JoinKind::Right,
+            JoinKind::Right => JoinKind::Left,
+            kind => kind,
+        }
+    }
+}
+
+/// One output row. Either side is `None` when an outer join found no match for the
+/// other; semi and anti joins only fill in `probe`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JoinRow<P, B> {
+    pub probe: Option<P>,
+    pub build: Option<B>,
+}
+
+/// Joins `probe` against a hash table built from the smaller input.
+///
+/// When both inputs report an exact `size_hint` (slices, `Vec`s and other
+/// `ExactSizeIterator`s) and `probe` is shorter, the table is built from `probe` and
+/// `build` is streamed through it instead. Otherwise the table is built from `build`. The
+/// rows are the same either way, with the probe row in [`JoinRow::probe`] and the build
+/// row in [`JoinRow::build`]; only their order differs.
+///
+/// Built from `build`, rows are produced lazily in probe order; for right and full joins,
+/// the unmatched build rows follow once the probe side is exhausted. Built from `probe`,
+/// matches come in build order, followed by the probe rows a left, full, semi or anti
+/// join keeps without a match to pair them with.
+pub fn hash_join<B, P, K, FB, FP>(
+    build: impl IntoIterator<Item = B>,
+    probe: impl IntoIterator<Item = P>,
+    build_key: FB,
+    probe_key: FP,
+    kind: JoinKind,
+) -> HashJoin<B, P, K, impl Iterator<Item = B>, impl Iterator<Item = P>, FB, FP>
+where
+    B: Clone,
+    P: Clone,
+    K: Hash + Eq + Clone,
+    FB: Fn(&B) -> K,
+    FP: Fn(&P) -> K,
+{
+    let build = build.into_iter();
+    let probe = probe.into_iter();
+    let probe_is_smaller = match (exact_len(&build), exact_len(&probe)) {
+        (Some(build_len), Some(probe_len)) => probe_len < build_len,
+        _ => false,
+    };
+    let direction = if probe_is_smaller {
+        let mut table: HashMap<K, Vec<P>> = HashMap::new();
+        for row in probe {
+            table.entry(probe_key(&row)).or_default().push(row);
+        }
+        Direction::Swapped(Probing::swapped(table, build, build_key, kind))
+    } else {
+        let mut table: HashMap<K, Vec<B>> = HashMap::new();
+        for row in build {
+            table.entry(build_key(&row)).or_default().push(row);
+        }
+        Direction::Build(Probing::new(table, probe, probe_key, kind))
+    };
+    HashJoin { direction }
+}
+
+fn exact_len(iter: &impl Iterator) -> Option<usize> {
+    match iter.size_hint() {
+        (lower, Some(upper)) if lower == upper => Some(upper),
+        _ => None,
+    }
+}
+
+/// The iterator returned by [`hash_join`].
+pub struct HashJoin<B, P, K, IB, IP, FB, FP> {
+    direction: Direction<B, P, K, IB, IP, FB, FP>,
+}
+
+enum Direction<B, P, K, IB, IP, FB, FP> {
+    /// The table holds the build side and the probe side is streamed.
+    Build(Probing<B, P, K, IP, FP>),
+    /// The table holds the probe side and the build side is streamed, so the rows come
+    /// out with their columns exchanged.
+    Swapped(Probing<P, B, K, IB, FB>),
+}
+
+impl<B, P, K, IB, IP, FB, FP> Iterator for HashJoin<B, P, K, IB, IP, FB, FP>
+where
+    B: Clone,
+    P: Clone,
+    K: Hash + Eq + Clone,
+    IB: Iterator<Item = B>,
+    IP: Iterator<Item = P>,
+    FB: Fn(&B) -> K,
+    FP: Fn(&P) -> K,
+{
+    type Item = JoinRow<P, B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.direction {
+            Direction::Build(join) => join.next(),
+            Direction::Swapped(join) => join.next().map(|row| JoinRow {
+                probe: row.build,
+                build: row.probe,
+            }),
+        }
+    }
+}
+
+/// Streams `probe` rows against a table of `build` rows.
+struct Probing<B, P, K, I, FP> {
+    table: HashMap<K, Vec<B>>,
+    matched: HashSet<K>,
+    probe: I,
+    probe_key: FP,
+    kind: JoinKind,
+    /// Set for a semi or anti join whose table holds the join's probe side: streamed rows
+    /// only mark the keys they match, and once they run out the table rows that were
+    /// (semi) or were not (anti) matched are emitted.
+    filter_table: bool,
+    pending: VecDeque<JoinRow<P, B>>,
+    unmatched: Option<hash_map::IntoIter<K, Vec<B>>>,
+}
+
+impl<B, P, K, I, FP> Probing<B, P, K, I, FP>
+where
+    B: Clone,
+    P: Clone,
+    K: Hash + Eq + Clone,
+    I: Iterator<Item = P>,
+    FP: Fn(&P) -> K,
+{
+    fn new(table: HashMap<K, Vec<B>>, probe: I, probe_key: FP, kind: JoinKind) -> Self {
+        Probing {
+            table,
+            matched: HashSet::new(),
+            probe,
+            probe_key,
+            kind,
+            filter_table: false,
+            pending: VecDeque::new(),
+            unmatched: None,
+        }
+    }
+
+    /// A join of the kind `kind` whose probe side is in `table`, streaming its build side.
+    fn swapped(table: HashMap<K, Vec<B>>, build: I, build_key: FP, kind: JoinKind) -> Self {
+        let mut join = Probing::new(table, build, build_key, kind.swapped());
+        join.filter_table = matches!(kind, JoinKind::Semi | JoinKind::Anti);
+        join
+    }
+
+    fn keeps_table_rows(&self) -> bool {
+        self.filter_table || self.kind.keeps_unmatched_build()
+    }
+
+    fn probe_row(&mut self, row: P) {
+        let key = (self.probe_key)(&row);
+        if self.filter_table {
+            if self.table.contains_key(&key) {
+                self.matched.insert(key);
+            }
+            return;
+        }
+        match (self.table.get(&key), self.kind) {
+            (Some(_), JoinKind::Semi)
+            | (None, JoinKind::Left | JoinKind::Full | JoinKind::Anti) => {
+                self.pending.push_back(JoinRow {
+                    probe: Some(row),
+                    build: None,
+                })
+            }
+            (Some(_), JoinKind::Anti) | (None, _) => {}
+            (Some(matches), _) => {
+                for build in matches {
+                    self.pending.push_back(JoinRow {
+                        probe: Some(row.clone()),
+                        build: Some(build.clone()),
+                    });
+                }
+                if self.kind.keeps_unmatched_build() {
+                    self.matched.insert(key);
+                }
+            }
+        }
+    }
+}
+
+impl<B, P, K, I, FP> Iterator for Probing<B, P, K, I, FP>
+where
+    B: Clone,
+    P: Clone,
+    K: Hash + Eq + Clone,
+    I: Iterator<Item = P>,
+    FP: Fn(&P) -> K,
+{
+    type Item = JoinRow<P, B>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Some(row);
+            }
+            if let Some(unmatched) = &mut self.unmatched {
+                let (key, rows) = unmatched.next()?;
+                // Only a semi join filtering the table keeps the matched rows here.
+                let semi = self.filter_table && self.kind == JoinKind::Semi;
+                if self.matched.contains(&key) == semi {
+                    self.pending.extend(rows.into_iter().map(|build| JoinRow {
+                        probe: None,
+                        build: Some(build),
+                    }));
+                }
+            } else if let Some(row) = self.probe.next() {
+                self.probe_row(row);
+            } else if self.keeps_table_rows() {
+                self.unmatched = Some(mem::take(&mut self.table).into_iter());
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+/// When and where [`hash_join_with_spill`] spills to disk.
+#[derive(Debug, Clone)]
+pub struct SpillConfig {
+    memory_budget: usize,
+    dir: PathBuf,
+    partitions: usize,
+}
+
+impl SpillConfig {
+    /// Spills once the build side's estimated size exceeds `memory_budget` bytes.
+    ///
+    /// Spill files go to the system temp directory and both sides are split into 16
+    /// partitions unless configured otherwise.
+    pub fn new(memory_budget: usize) -> Self {
+        SpillConfig {
+            memory_budget,
+            dir: std::env::temp_dir(),
+            partitions: 16,
+        }
+    }
+
+    pub fn dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Sets the number of partitions. Each build partition must fit in memory on its own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is zero.
+    pub fn partitions(mut self, partitions: usize) -> Self {
+        assert!(partitions > 0, "need at least one partition");
+        self.partitions = partitions;
+        self
+    }
+}
+
+/// Like [`hash_join`], but partitions both sides to disk if the build side grows past
+/// `config`'s memory budget, as estimated from each row's serialized size.
+///
+/// The returned iterator streams its output: after a spill it holds one build partition
+/// in memory at a time and reads probe rows back one by one. The table is always built
+/// from `build`, whose size the budget is measured against. Without a spill, rows come
+/// out in probe order; after a spill they are grouped by partition instead. Reading a spill file can fail, so rows are `io::Result`s; the
+/// iterator ends after the first error.
+pub fn hash_join_with_spill<B, P, K, I, FB, FP>(
+    build: impl IntoIterator<Item = B>,
+    probe: I,
+    build_key: FB,
+    probe_key: FP,
+    kind: JoinKind,
+    config: &SpillConfig,
+) -> io::Result<SpillJoin<B, P, K, I::IntoIter, FB, FP>>
+where
+    B: Clone + Serialize + DeserializeOwned,
+    P: Clone + Serialize + DeserializeOwned,
+    K: Hash + Eq + Clone,
+    I: IntoIterator<Item = P>,
+    FB: Fn(&B) -> K,
+    FP: Fn(&P) -> K + Clone,
+{
+    let mut build = build.into_iter();
+    let mut table: HashMap<K, Vec<B>> = HashMap::new();
+    let mut estimated = 0;
+    for row in build.by_ref() {
+        estimated += mem::size_of::<B>() + encoded_size(&row)?;
+        table.entry(build_key(&row)).or_default().push(row);
+        if estimated > config.memory_budget {
+            break;
+        }
+    }
+    if estimated <= config.memory_budget {
+        return Ok(SpillJoin {
+            state: SpillState::InMemory(Probing::new(table, probe.into_iter(), probe_key, kind)),
+        });
+    }
+
+    let build_files = Partitions::create(config, "build");
+    let probe_files = Partitions::create(config, "probe");
+    let mut writers = build_files.writers()?;
+    for (key, rows) in table {
+        let partition = partition_of(&key, config.partitions);
+        for row in rows {
+            write_row(&mut writers[partition], &row)?;
+        }
+    }
+    for row in build {
+        write_row(
+            &mut writers[partition_of(&build_key(&row), config.partitions)],
+            &row,
+        )?;
+    }
+    finish(writers)?;
+
+    let mut writers = probe_files.writers()?;
+    for row in probe {
+        write_row(
+            &mut writers[partition_of(&probe_key(&row), config.partitions)],
+            &row,
+        )?;
+    }
+    finish(writers)?;
+
+    Ok(SpillJoin {
+        state: SpillState::Spilled(Spilled {
+            build_files,
+            probe_files,
+            next_partition: 0,
+            current: None,
+            build_key,
+            probe_key,
+            kind,
+        }),
+    })
+}
+
+/// The iterator returned by [`hash_join_with_spill`].
+pub struct SpillJoin<B, P, K, I, FB, FP> {
+    state: SpillState<B, P, K, I, FB, FP>,
+}
+
+enum SpillState<B, P, K, I, FB, FP> {
+    InMemory(Probing<B, P, K, I, FP>),
+    Spilled(Spilled<B, P, K, FB, FP>),
+}
+
+/// A join over partitions on disk, taken one pair at a time.
+struct Spilled<B, P, K, FB, FP> {
+    build_files: Partitions,
+    probe_files: Partitions,
+    next_partition: usize,
+    current: Option<Probing<B, P, K, RowReader<P>, FP>>,
+    build_key: FB,
+    probe_key: FP,
+    kind: JoinKind,
+}
+
+impl<B, P, K, FB, FP> Spilled<B, P, K, FB, FP>
+where
+    B: Clone + DeserializeOwned,
+    P: Clone + DeserializeOwned,
+    K: Hash + Eq + Clone,
+    FB: Fn(&B) -> K,
+    FP: Fn(&P) -> K + Clone,
+{
+    fn load(&self, partition: usize) -> io::Result<Probing<B, P, K, RowReader<P>, FP>> {
+        let mut table: HashMap<K, Vec<B>> = HashMap::new();
+        let mut rows = RowReader::<B>::open(&self.build_files.paths[partition])?;
+        for row in rows.by_ref() {
+            table.entry((self.build_key)(&row)).or_default().push(row);
+        }
+        if let Some(e) = rows.error {
+            return Err(e);
+        }
+        let probe = RowReader::open(&self.probe_files.paths[partition])?;
+        Ok(Probing::new(
+            table,
+            probe,
+            self.probe_key.clone(),
+            self.kind,
+        ))
+    }
+
+    fn next(&mut self) -> Option<io::Result<JoinRow<P, B>>> {
+        loop {
+            if let Some(join) = &mut self.current {
+                let row = join.next();
+                if let Some(e) = join.probe.error.take() {
+                    self.fail();
+                    return Some(Err(e));
+                }
+                match row {
+                    Some(row) => return Some(Ok(row)),
+                    None => self.current = None,
+                }
+            }
+            if self.next_partition == self.build_files.paths.len() {
+                return None;
+            }
+            match self.load(self.next_partition) {
+                Ok(join) => {
+                    self.current = Some(join);
+                    self.next_partition += 1;
+                }
+                Err(e) => {
+                    self.fail();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    fn fail(&mut self) {
+        self.current = None;
+        self.next_partition = self.build_files.paths.len();
+    }
+}
+
+impl<B, P, K, I, FB, FP> Iterator for SpillJoin<B, P, K, I, FB, FP>
+where
+    B: Clone + DeserializeOwned,
+    P: Clone + DeserializeOwned,
+    K: Hash + Eq + Clone,
+    I: Iterator<Item = P>,
+    FB: Fn(&B) -> K,
+    FP: Fn(&P) -> K + Clone,
+{
+    type Item = io::Result<JoinRow<P, B>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.state {
+            SpillState::InMemory(join) => join.next().map(Ok),
+            SpillState::Spilled(spilled) => spilled.next(),
+        }
+    }
+}
+
+fn partition_of<K: Hash>(key: &K, partitions: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % partitions as u64) as usize
+}
+
+fn encoded_size<T: Serialize>(row: &T) -> io::Result<usize> {
+    bincode::serialized_size(row)
+        .map(|size| size as usize)
+        .map_err(io::Error::other)
+}
+
+fn write_row<T: Serialize>(writer: &mut BufWriter<File>, row: &T) -> io::Result<()> {
+    bincode::serialize_into(writer, row).map_err(io::Error::other)
+}
+
+fn finish(writers: Vec<BufWriter<File>>) -> io::Result<()> {
+    for mut writer in writers {
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Reads back rows written by `write_row`. Iteration stops at the first error, which is
+/// kept in `error`.
+struct RowReader<T> {
+    reader: BufReader<File>,
+    error: Option<io::Error>,
+    row: PhantomData<T>,
+}
+
+impl<T> RowReader<T> {
+    fn open(path: &Path) -> io::Result<Self> {
+        Ok(RowReader {
+            reader: BufReader::new(File::open(path)?),
+            error: None,
+            row: PhantomData,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for RowReader<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+        let result = match self.reader.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => bincode::deserialize_from(&mut self.reader).map_err(io::Error::other),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| self.error = Some(e)).ok()
+    }
+}
+
+/// Spill files for one side of a join, deleted on drop.
+struct Partitions {
+    paths: Vec<PathBuf>,
+}
+
+impl Partitions {
+    fn create(config: &SpillConfig, side: &str) -> Partitions {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let paths = (0..config.partitions)
+            .map(|i| {
+                config.dir.join(format!(
+                    "hash_join_{}_{}_{}_{}.spill",
+                    std::process::id(),
+                    id,
+                    side,
+                    i
+                ))
+            })
+            .collect();
+        Partitions { paths }
+    }
+
+    fn writers(&self) -> io::Result<Vec<BufWriter<File>>> {
+        self.paths
+            .iter()
+            .map(|path| File::create(path).map(BufWriter::new))
+            .collect()
+    }
+}
+
+impl Drop for Partitions {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Customer = (u32, String);
+    type Order = (u32, u32, i64);
+
+    fn customers() -> Vec<Customer> {
+        vec![
+            (1, "alice".to_string()),
+            (2, "bob".to_string()),
+            (3, "carol".to_string()),
+        ]
+    }
+
+    fn orders() -> Vec<Order> {
+        // (order id, customer id, amount)
+        vec![(10, 1, 5), (11, 4, 7), (12, 1, 9), (13, 2, 1)]
+    }
+
+    fn join(kind: JoinKind) -> Vec<(Option<u32>, Option<u32>)> {
+        hash_join(customers(), orders(), |c| c.0, |o| o.1, kind)
+            .map(|row| (row.probe.map(|o| o.0), row.build.map(|c| c.0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_join_kinds() {
+        let matches = [
+            (Some(10), Some(1)),
+            (Some(12), Some(1)),
+            (Some(13), Some(2)),
+        ];
+        assert_eq!(join(JoinKind::Inner), matches);
+        assert_eq!(
+            join(JoinKind::Left),
+            [matches[0], (Some(11), None), matches[1], matches[2]]
+        );
+        assert_eq!(
+            join(JoinKind::Right),
+            [&matches[..], &[(None, Some(3))]].concat()
+        );
+        assert_eq!(
+            join(JoinKind::Full),
+            [
+                matches[0],
+                (Some(11), None),
+                matches[1],
+                matches[2],
+                (None, Some(3))
+            ]
+        );
+        assert_eq!(
+            join(JoinKind::Semi),
+            [(Some(10), None), (Some(12), None), (Some(13), None)]
+        );
+        assert_eq!(join(JoinKind::Anti), [(Some(11), None)]);
+    }
+
+    #[test]
+    fn test_duplicate_build_keys_multiply() {
+        let build = [(1, 'a'), (1, 'b'), (2, 'c')];
+        let probe = [1, 1, 2];
+        let rows: Vec<_> = hash_join(build, probe, |b| b.0, |p| *p, JoinKind::Inner).collect();
+        assert_eq!(rows.len(), 5);
+        let semi = hash_join(build, probe, |b| b.0, |p| *p, JoinKind::Semi);
+        assert_eq!(semi.count(), 3);
+    }
+
+    #[test]
+    fn test_builds_from_the_smaller_side() {
+        // Three orders against five customers: the orders are smaller, so the table is
+        // built from them. Hiding the length of the customers forces the other way round.
+        let customers: Vec<Customer> = (0..5).map(|i| (i % 4, format!("c{}", i))).collect();
+        let orders: Vec<Order> = vec![(10, 1, 5), (11, 9, 7), (12, 0, 9)];
+
+        for kind in [
+            JoinKind::Inner,
+            JoinKind::Left,
+            JoinKind::Right,
+            JoinKind::Full,
+            JoinKind::Semi,
+            JoinKind::Anti,
+        ] {
+            let swapped = hash_join(customers.clone(), orders.clone(), |c| c.0, |o| o.1, kind);
+            assert!(matches!(swapped.direction, Direction::Swapped(_)));
+            let unsized_customers = customers.clone().into_iter().filter(|_| true);
+            let built = hash_join(unsized_customers, orders.clone(), |c| c.0, |o| o.1, kind);
+            assert!(matches!(built.direction, Direction::Build(_)));
+
+            let mut swapped: Vec<_> = swapped.collect();
+            let mut built: Vec<_> = built.collect();
+            swapped.sort();
+            built.sort();
+            assert_eq!(swapped, built, "{:?}", kind);
+            if kind == JoinKind::Semi {
+                let ids: Vec<_> = swapped
+                    .iter()
+                    .map(|row| row.probe.as_ref().unwrap().0)
+                    .collect();
+                assert_eq!(ids, [10, 12]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_spill_matches_in_memory_join() {
+        let dir = std::env::temp_dir().join(format!("hash_join_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let build: Vec<Customer> = (0..500).map(|i| (i, format!("customer-{}", i))).collect();
+        let probe: Vec<Order> = (0..2_000).map(|i| (i, (i * 7) % 600, i as i64)).collect();
+
+        for kind in [
+            JoinKind::Inner,
+            JoinKind::Left,
+            JoinKind::Right,
+            JoinKind::Full,
+            JoinKind::Semi,
+            JoinKind::Anti,
+        ] {
+            let mut expected: Vec<_> =
+                hash_join(build.clone(), probe.clone(), |c| c.0, |o| o.1, kind).collect();
+            expected.sort();
+            for budget in [usize::MAX, 1_000] {
+                let config = SpillConfig::new(budget).dir(&dir).partitions(7);
+                let mut rows = hash_join_with_spill(
+                    build.clone(),
+                    probe.clone(),
+                    |c| c.0,
+                    |o| o.1,
+                    kind,
+                    &config,
+                )
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap();
+                rows.sort();
+                assert_eq!(rows, expected, "{:?} with budget {}", kind, budget);
+            }
+        }
+        assert_eq!(
+            fs::read_dir(&dir).unwrap().count(),
+            0,
+            "spill files left behind"
+        );
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn test_spill_streams_and_cleans_up_early() {
+        let dir = std::env::temp_dir().join(format!("hash_join_stream_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let build: Vec<Customer> = (0..200).map(|i| (i, format!("customer-{}", i))).collect();
+        let probe: Vec<Order> = (0..1_000).map(|i| (i, i % 200, 1)).collect();
+        let config = SpillConfig::new(100).dir(&dir).partitions(4);
+
+        let mut rows =
+            hash_join_with_spill(build, probe, |c| c.0, |o| o.1, JoinKind::Inner, &config).unwrap();
+        assert!(rows.next().unwrap().is_ok());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 8);
+        drop(rows);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(dir).unwrap();
+    }
+}
@@ -0,0 +1,15 @@
+//! Hash map implementations to compare against `std::collections::HashMap`, and the
+//! `HashMap`-based data processing built on top of them.
+
+pub mod consistent_hash;
+pub mod group_by;
+pub mod inverted_index;
+pub mod join;
+pub mod robin_hood;
+pub mod tokenizer;
+
+pub use consistent_hash::ConsistentHashRing;
+pub use group_by::{Agg, AggRow, GroupBy, Value};
+pub use inverted_index::{DocId, InvertedIndex, Query};
+pub use join::{hash_join, hash_join_with_spill, JoinKind, JoinRow, SpillConfig, SpillJoin};
+pub use robin_hood::RobinHoodMap;
@@ -0,0 +1,840 @@
+//! An open-addressing hash map using robin-hood hashing.
+//!
+//! Entries live directly in one power-of-two sized array and collisions are resolved by
+//! linear probing. On insert, an entry that is further from its ideal slot than the one
+//! occupying a slot takes that slot and the displaced entry keeps probing ("robin hood":
+//! take from the rich, give to the poor). This keeps probe sequences short and uniform,
+//! and lets a lookup stop as soon as it meets an entry closer to home than the key would
+//! be. Removal shifts the following entries back by one slot instead of leaving
+//! tombstones, so the table never needs cleaning up.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::RobinHoodMap;
+//!
+//! let mut counts = RobinHoodMap::new();
+//! for word in "the cat sat on the mat".split_whitespace() {
+//!     *counts.entry(word).or_insert(0) += 1;
+//! }
+//! assert_eq!(counts.get("the"), Some(&2));
+//! assert_eq!(counts.remove("cat"), Some(1));
+//! assert_eq!(counts.len(), 4);
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::Index;
+
+const DEFAULT_MAX_LOAD_FACTOR: f64 = 0.875;
+const MIN_CAPACITY: usize = 8;
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+/// A hash map with open addressing, robin-hood displacement and backward-shift deletion.
+#[derive(Clone)]
+pub struct RobinHoodMap<K, V, S = RandomState> {
+    buckets: Vec<Option<Bucket<K, V>>>,
+    len: usize,
+    max_load_factor: f64,
+    hash_builder: S,
+}
+
+impl<K, V> RobinHoodMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    /// Creates a map that can hold `capacity` entries without growing.
+    pub fn with_capacity(capacity: usize) -> Self
+    where
+        K: Hash + Eq,
+    {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> RobinHoodMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
+        RobinHoodMap {
+            buckets: Vec::new(),
+            len: 0,
+            max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        (self.buckets.len() as f64 * self.max_load_factor) as usize
+    }
+
+    /// The fraction of slots that may be occupied before the table doubles.
+    pub fn max_load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// Removes all entries, keeping the allocated table.
+    pub fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    /// The longest distance any entry sits from its ideal slot.
+    pub fn max_probe_distance(&self) -> usize {
+        self.occupied()
+            .map(|(i, b)| self.probe_distance(b.hash, i))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The average distance of an entry from its ideal slot, i.e. the expected number of
+    /// extra slots a successful lookup inspects.
+    pub fn mean_probe_distance(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        let total: usize = self
+            .occupied()
+            .map(|(i, b)| self.probe_distance(b.hash, i))
+            .sum();
+        total as f64 / self.len as f64
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.buckets.iter(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.buckets.iter_mut(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    fn mask(&self) -> usize {
+        self.buckets.len() - 1
+    }
+
+    fn ideal(&self, hash: u64) -> usize {
+        hash as usize & self.mask()
+    }
+
+    fn probe_distance(&self, hash: u64, index: usize) -> usize {
+        index.wrapping_sub(self.ideal(hash)) & self.mask()
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = (usize, &Bucket<K, V>)> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().map(|b| (i, b)))
+    }
+
+    /// Places an entry known not to be in the map, returning the slot it ended up in.
+    ///
+    /// The table must have a free slot.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        let mask = self.mask();
+        let mut carried = Bucket { hash, key, value };
+        let mut index = self.ideal(hash);
+        let mut distance = 0;
+        let mut placed = None;
+        loop {
+            match &mut self.buckets[index] {
+                slot @ None => {
+                    *slot = Some(carried);
+                    self.len += 1;
+                    return placed.unwrap_or(index);
+                }
+                Some(resident) => {
+                    let resident_distance = index.wrapping_sub(resident.hash as usize) & mask;
+                    if resident_distance < distance {
+                        mem::swap(resident, &mut carried);
+                        placed.get_or_insert(index);
+                        distance = resident_distance;
+                    }
+                }
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Removes the entry in slot `index`, shifting the entries after it back.
+    fn remove_at(&mut self, index: usize) -> Bucket<K, V> {
+        let mask = self.mask();
+        let removed = self.buckets[index].take().expect("slot is occupied");
+        let mut hole = index;
+        loop {
+            let next = (hole + 1) & mask;
+            match &self.buckets[next] {
+                Some(b) if self.probe_distance(b.hash, next) > 0 => {
+                    self.buckets[hole] = self.buckets[next].take();
+                    hole = next;
+                }
+                _ => break,
+            }
+        }
+        self.len -= 1;
+        removed
+    }
+}
+
+impl<K, V, S> RobinHoodMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut map = Self::with_hasher(hash_builder);
+        map.reserve(capacity);
+        map
+    }
+
+    /// Sets the maximum load factor, growing the table if needed to keep its capacity.
+    ///
+    /// Lower values trade memory for shorter probe sequences.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < load_factor < 1`.
+    pub fn with_max_load_factor(mut self, load_factor: f64) -> Self {
+        assert!(
+            load_factor > 0.0 && load_factor < 1.0,
+            "load factor must be between 0 and 1, got {}",
+            load_factor
+        );
+        let capacity = self.capacity();
+        self.max_load_factor = load_factor;
+        self.reserve(capacity.saturating_sub(self.len));
+        self
+    }
+
+    /// Makes room for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len + additional;
+        if needed == 0 || needed <= self.capacity() {
+            return;
+        }
+        let mut slots = self.buckets.len().max(MIN_CAPACITY);
+        while (slots as f64 * self.max_load_factor) < needed as f64 {
+            slots *= 2;
+        }
+        if slots != self.buckets.len() {
+            self.resize(slots);
+        }
+    }
+
+    /// Shrinks the table as far as the load factor allows.
+    pub fn shrink_to_fit(&mut self) {
+        let mut slots = MIN_CAPACITY;
+        while (slots as f64 * self.max_load_factor) < self.len as f64 {
+            slots *= 2;
+        }
+        if self.len == 0 {
+            self.buckets = Vec::new();
+        } else if slots < self.buckets.len() {
+            self.resize(slots);
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash(key), key)?;
+        self.buckets[index].as_ref().map(|b| &b.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash(key), key)?;
+        self.buckets[index].as_ref().map(|b| (&b.key, &b.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash(key), key)?;
+        self.buckets[index].as_mut().map(|b| &mut b.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hash(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash(key), key)?;
+        let bucket = self.remove_at(index);
+        Some((bucket.key, bucket.value))
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        // Removing from the last slot can shift an entry that wrapped around to slot 0,
+        // and was already visited, back into it; stopping once every entry has been seen
+        // keeps `keep` to one call per entry.
+        let mut unvisited = self.len;
+        let mut index = 0;
+        while unvisited > 0 && index < self.buckets.len() {
+            let remove = match &mut self.buckets[index] {
+                Some(b) => {
+                    unvisited -= 1;
+                    !keep(&b.key, &mut b.value)
+                }
+                None => false,
+            };
+            if remove {
+                // The next entry may have been shifted into this slot.
+                self.remove_at(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// Gets the entry for `key` for in-place manipulation.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash(&key);
+        if let Some(index) = self.find(hash, &key) {
+            return Entry::Occupied(OccupiedEntry { map: self, index });
+        }
+        self.reserve(1);
+        Entry::Vacant(VacantEntry {
+            map: self,
+            hash,
+            key,
+        })
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let mut index = self.ideal(hash);
+        let mut distance = 0;
+        while let Some(b) = &self.buckets[index] {
+            if distance > self.probe_distance(b.hash, index) {
+                // Robin-hood invariant: the key would have displaced this entry.
+                return None;
+            }
+            if b.hash == hash && b.key.borrow() == key {
+                return Some(index);
+            }
+            index = (index + 1) & self.mask();
+            distance += 1;
+        }
+        None
+    }
+
+    fn resize(&mut self, slots: usize) {
+        let old = mem::replace(&mut self.buckets, (0..slots).map(|_| None).collect());
+        self.len = 0;
+        for b in old.into_iter().flatten() {
+            self.insert_new(b.hash, b.key, b.value);
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for RobinHoodMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for RobinHoodMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> PartialEq for RobinHoodMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for RobinHoodMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for RobinHoodMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for RobinHoodMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for RobinHoodMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+/// A view into a single entry of a [`RobinHoodMap`], which may be vacant or occupied.
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V, S> {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    map: &'a mut RobinHoodMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    fn bucket(&self) -> &Bucket<K, V> {
+        self.map.buckets[self.index].as_ref().unwrap()
+    }
+
+    fn bucket_mut(&mut self) -> &mut Bucket<K, V> {
+        self.map.buckets[self.index].as_mut().unwrap()
+    }
+
+    pub fn key(&self) -> &K {
+        &self.bucket().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.bucket().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.bucket_mut().value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.buckets[self.index].as_mut().unwrap().value
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let bucket = self.map.remove_at(self.index);
+        (bucket.key, bucket.value)
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.map.insert_new(self.hash, self.key, value);
+        &mut self.map.buckets[index].as_mut().unwrap().value
+    }
+}
+
+/// An iterator over the entries of a [`RobinHoodMap`], in table order.
+pub struct Iter<'a, K, V> {
+    inner: std::slice::Iter<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let b = self.inner.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((&b.key, &b.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// A mutable iterator over the entries of a [`RobinHoodMap`].
+pub struct IterMut<'a, K, V> {
+    inner: std::slice::IterMut<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let b = self.inner.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((&b.key, &mut b.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// An owning iterator over the entries of a [`RobinHoodMap`].
+pub struct IntoIter<K, V> {
+    inner: std::vec::IntoIter<Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let b = self.inner.by_ref().flatten().next()?;
+        self.remaining -= 1;
+        Some((b.key, b.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V, S> IntoIterator for RobinHoodMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            inner: self.buckets.into_iter(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a RobinHoodMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut RobinHoodMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    /// A hasher that keeps only the low bits of the key, to force long probe chains.
+    #[derive(Default)]
+    struct Clustering(u64);
+
+    impl Hasher for Clustering {
+        fn finish(&self) -> u64 {
+            self.0 & 0b11
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(b as u64);
+            }
+        }
+    }
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    /// Applies the same random operations to `map` and a std `HashMap`, checking that
+    /// they agree after every step.
+    fn differential<S: BuildHasher>(mut map: RobinHoodMap<u16, u32, S>, seed: u64, keys: u64) {
+        let mut model = HashMap::new();
+        let mut rng = XorShift(seed);
+        for step in 0..5_000 {
+            let key = rng.next(keys) as u16;
+            let value = rng.next(1_000) as u32;
+            match rng.next(100) {
+                0..=39 => assert_eq!(map.insert(key, value), model.insert(key, value)),
+                40..=59 => assert_eq!(map.remove(&key), model.remove(&key)),
+                60..=74 => assert_eq!(map.get(&key), model.get(&key)),
+                75..=89 => {
+                    *map.entry(key).and_modify(|v| *v += 1).or_insert(value) += 1;
+                    *model.entry(key).and_modify(|v| *v += 1).or_insert(value) += 1;
+                }
+                90..=94 => {
+                    map.retain(|k, _| k % 3 != 0);
+                    model.retain(|k, _| k % 3 != 0);
+                }
+                95..=97 => {
+                    if let Entry::Occupied(entry) = map.entry(key) {
+                        assert_eq!(Some(entry.remove()), model.remove(&key));
+                    }
+                }
+                _ => map.shrink_to_fit(),
+            }
+            assert_eq!(map.len(), model.len(), "seed {} step {}", seed, step);
+            assert!(map.len() <= map.capacity());
+        }
+        let mut entries: Vec<_> = map.into_iter().collect();
+        entries.sort_unstable();
+        let mut expected: Vec<_> = model.into_iter().collect();
+        expected.sort_unstable();
+        assert_eq!(entries, expected, "seed {}", seed);
+    }
+
+    #[test]
+    fn test_differential_against_std_hashmap() {
+        for seed in 1..=20 {
+            differential(RobinHoodMap::new(), seed, 256);
+            differential(RobinHoodMap::new().with_max_load_factor(0.5), seed, 64);
+            differential(
+                RobinHoodMap::with_hasher(BuildHasherDefault::<Clustering>::default())
+                    .with_max_load_factor(0.95),
+                seed,
+                512,
+            );
+        }
+    }
+
+    #[test]
+    fn test_retain_visits_each_entry_once() {
+        let mut rng = XorShift(0x9e37_79b9);
+        for _ in 0..2_000 {
+            let len = rng.next(64) as usize;
+            let keys: Vec<u64> = (0..len).map(|_| rng.next(1 << 20)).collect();
+            let mut map: RobinHoodMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+            let mut model: HashMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+
+            let salt = rng.next(u64::MAX);
+            let keep = |k: &u64| !(k ^ salt).is_multiple_of(3);
+            let mut calls: HashMap<u64, usize> = HashMap::new();
+            map.retain(|k, _| {
+                *calls.entry(*k).or_default() += 1;
+                keep(k)
+            });
+            model.retain(|k, _| keep(k));
+
+            let distinct: HashSet<u64> = keys.iter().copied().collect();
+            assert_eq!(calls.keys().copied().collect::<HashSet<_>>(), distinct);
+            assert!(calls.values().all(|&n| n == 1), "{:?}", calls);
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_unstable();
+            let mut expected: Vec<_> = model.into_iter().collect();
+            expected.sort_unstable();
+            assert_eq!(entries, expected);
+        }
+    }
+
+    #[test]
+    fn test_string_keys_borrow_as_str() {
+        let mut map: RobinHoodMap<String, usize> =
+            (0..1_000).map(|i| (format!("key-{}", i), i)).collect();
+        assert_eq!(map.get("key-500"), Some(&500));
+        assert_eq!(map["key-999"], 999);
+        assert!(!map.contains_key("key-1000"));
+        for value in map.values_mut() {
+            *value *= 2;
+        }
+        assert_eq!(map.remove("key-500"), Some(1000));
+        assert_eq!(map.iter().len(), 999);
+        assert_eq!(map.values().sum::<usize>(), 999 * 1000 - 1000);
+    }
+
+    #[test]
+    fn test_load_factor_bounds_growth_and_probes() {
+        let mut map = RobinHoodMap::with_capacity(100).with_max_load_factor(0.5);
+        let slots = map.buckets.len();
+        assert!(map.capacity() >= 100);
+        map.extend((0..100).map(|i| (i, i)));
+        assert_eq!(map.buckets.len(), slots);
+        assert!(map.len() as f64 / slots as f64 <= 0.5);
+        assert!(map.mean_probe_distance() < 1.0);
+
+        map.retain(|k, _| *k < 10);
+        map.shrink_to_fit();
+        assert_eq!(map.buckets.len(), 32);
+        assert_eq!(map.len(), 10);
+    }
+
+    #[test]
+    fn test_backward_shift_leaves_no_gaps() {
+        let mut map = RobinHoodMap::with_hasher(BuildHasherDefault::<Clustering>::default());
+        map.extend((0..6u8).map(|i| (i, ())));
+        for i in 0..6u8 {
+            map.remove(&i);
+            // Every remaining entry must still be reachable from its ideal slot.
+            for j in i + 1..6 {
+                assert!(map.contains_key(&j), "lost {} after removing {}", j, i);
+            }
+        }
+        assert!(map.buckets.iter().all(Option::is_none));
+    }
+
+    #[test]
+    #[should_panic(expected = "load factor must be between 0 and 1")]
+    fn test_rejects_full_load_factor() {
+        let _ = RobinHoodMap::<u8, u8>::new().with_max_load_factor(1.0);
+    }
+}
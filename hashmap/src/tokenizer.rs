@@ -0,0 +1,186 @@
+//! Tokenizers for the [`InvertedIndex`](crate::inverted_index::InvertedIndex).
+//!
+//! [`Whitespace`] splits text into words; [`Lowercase`], [`StopWords`] and [`NGrams`]
+//! wrap another tokenizer to transform its output, and are usually chained through
+//! [`TokenizerExt`]. Each token keeps the position of the word it came from, so removing
+//! stop words leaves gaps rather than shifting later words, and all n-grams of a word
+//! share its position.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashmap::tokenizer::{Tokenizer, TokenizerExt, Whitespace};
+//!
+//! let tokenizer = Whitespace.lowercase().stop_words(["the", "is"]);
+//! let tokens: Vec<_> = tokenizer
+//!     .tokenize("The disk is FULL")
+//!     .into_iter()
+//!     .map(|t| (t.text, t.position))
+//!     .collect();
+//! assert_eq!(tokens, [("disk".to_string(), 1), ("full".to_string(), 3)]);
+//! ```
+
+use std::collections::HashSet;
+
+/// A term and the position of the word it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub position: usize,
+}
+
+/// Splits text into [`Token`]s.
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Token>;
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for Box<T> {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        (**self).tokenize(text)
+    }
+}
+
+/// Splits on Unicode whitespace, leaving words unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Whitespace;
+
+impl Tokenizer for Whitespace {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        text.split_whitespace()
+            .enumerate()
+            .map(|(position, word)| Token {
+                text: word.to_string(),
+                position,
+            })
+            .collect()
+    }
+}
+
+/// Lowercases the tokens of another tokenizer.
+#[derive(Debug, Clone)]
+pub struct Lowercase<T>(pub T);
+
+impl<T: Tokenizer> Tokenizer for Lowercase<T> {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = self.0.tokenize(text);
+        for token in &mut tokens {
+            token.text = token.text.to_lowercase();
+        }
+        tokens
+    }
+}
+
+/// Drops the tokens of another tokenizer that are in a stop-word list.
+#[derive(Debug, Clone)]
+pub struct StopWords<T> {
+    inner: T,
+    words: HashSet<String>,
+}
+
+impl<T> StopWords<T> {
+    pub fn new<I, S>(inner: T, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        StopWords {
+            inner,
+            words: words.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for StopWords<T> {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut tokens = self.inner.tokenize(text);
+        tokens.retain(|token| !self.words.contains(&token.text));
+        tokens
+    }
+}
+
+/// Replaces each token of another tokenizer with its character n-grams, so a search can
+/// match part of a word. Tokens shorter than `n` are kept whole.
+#[derive(Debug, Clone)]
+pub struct NGrams<T> {
+    inner: T,
+    n: usize,
+}
+
+impl<T> NGrams<T> {
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn new(inner: T, n: usize) -> Self {
+        assert!(n > 0, "n-gram length must be positive");
+        NGrams { inner, n }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for NGrams<T> {
+    fn tokenize(&self, text: &str) -> Vec<Token> {
+        let mut grams = Vec::new();
+        for token in self.inner.tokenize(text) {
+            let chars: Vec<char> = token.text.chars().collect();
+            if chars.len() <= self.n {
+                grams.push(token);
+                continue;
+            }
+            grams.extend(chars.windows(self.n).map(|window| Token {
+                text: window.iter().collect(),
+                position: token.position,
+            }));
+        }
+        grams
+    }
+}
+
+/// Chaining adapters for any [`Tokenizer`].
+pub trait TokenizerExt: Tokenizer + Sized {
+    fn lowercase(self) -> Lowercase<Self> {
+        Lowercase(self)
+    }
+
+    fn stop_words<I, S>(self, words: I) -> StopWords<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        StopWords::new(self, words)
+    }
+
+    fn ngrams(self, n: usize) -> NGrams<Self> {
+        NGrams::new(self, n)
+    }
+}
+
+impl<T: Tokenizer> TokenizerExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(tokens: Vec<Token>) -> Vec<String> {
+        tokens.into_iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_ngrams_share_word_position() {
+        let tokens = Whitespace.lowercase().ngrams(3).tokenize("Disk OK");
+        assert_eq!(texts(tokens.clone()), ["dis", "isk", "ok"]);
+        assert_eq!(
+            tokens.iter().map(|t| t.position).collect::<Vec<_>>(),
+            [0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_stop_words_apply_after_lowercase() {
+        let tokenizer = Whitespace.lowercase().stop_words(["a", "the"]);
+        assert_eq!(
+            texts(tokenizer.tokenize("A timeout\tin THE  pool")),
+            ["timeout", "in", "pool"]
+        );
+        let boxed: Box<dyn Tokenizer> = Box::new(tokenizer);
+        assert_eq!(boxed.tokenize("the").len(), 0);
+    }
+}
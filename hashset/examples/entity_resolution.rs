@@ -0,0 +1,81 @@
+//! Resolves customer records into entities: two records belong to the same entity when
+//! they share any identifier, directly or through a chain of other records.
+//!
+//! ```text
+//! cargo run --example entity_resolution [records.csv] [id columns...]
+//! ```
+//!
+//! Defaults to `fixtures/customers.csv` linked on `email`, `phone` and `national_id`.
+
+use hashset::DisjointSet;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args.next().map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/customers.csv")
+    });
+    let mut id_columns: Vec<String> = args.collect();
+    if id_columns.is_empty() {
+        id_columns = vec!["email".into(), "phone".into(), "national_id".into()];
+    }
+
+    let mut reader = csv::Reader::from_path(&path)?;
+    let headers = reader.headers()?.clone();
+    let positions = id_columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|h| h == column)
+                .ok_or_else(|| format!("no column {:?} in {}", column, path.display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Records are added one at a time and linked to the first record seen with each of
+    // their identifiers, so the structure stays current as a stream is read.
+    let mut entities = DisjointSet::new();
+    let mut first_seen: HashMap<(usize, String), usize> = HashMap::new();
+    let mut records = Vec::new();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        entities.add(row);
+        for &column in &positions {
+            let id = record[column].trim();
+            if id.is_empty() {
+                continue;
+            }
+            let first = *first_seen.entry((column, id.to_string())).or_insert(row);
+            entities.union(first, row);
+        }
+        records.push(record);
+    }
+
+    let mut groups: Vec<Vec<usize>> = entities
+        .groups()
+        .into_values()
+        .map(|members| {
+            let mut members: Vec<usize> = members.into_iter().collect();
+            members.sort_unstable();
+            members
+        })
+        .collect();
+    groups.sort();
+
+    println!(
+        "{} records resolved into {} entities",
+        records.len(),
+        groups.len()
+    );
+    for (entity, members) in groups.iter().enumerate() {
+        println!("entity {}:", entity + 1);
+        for &row in members {
+            let fields: Vec<&str> = records[row].iter().collect();
+            println!("  {}", fields.join(", "));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+//! A disjoint-set forest (union-find) for grouping values that are transitively linked,
+//! such as records that share an email address or phone number.
+//!
+//! Each set is a tree whose root is its representative. [`union`](DisjointSet::union)
+//! hangs the shallower tree under the deeper one (union by rank) and
+//! [`find`](DisjointSet::find) points every value it passes straight at the root (path
+//! compression), which together make both effectively constant time.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashset::DisjointSet;
+//!
+//! let mut people = DisjointSet::new();
+//! people.union("ann@example.com", "+254 700 000001");
+//! people.union("+254 700 000001", "ann.w@example.com");
+//! people.add("bob@example.com");
+//!
+//! assert!(people.connected(&"ann@example.com", &"ann.w@example.com"));
+//! assert!(!people.connected(&"ann@example.com", &"bob@example.com"));
+//! assert_eq!(people.groups().len(), 2);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// A partition of values of type `T` into disjoint sets.
+#[derive(Debug, Clone)]
+pub struct DisjointSet<T> {
+    index: HashMap<T, usize>,
+    values: Vec<T>,
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl<T: Hash + Eq + Clone> Default for DisjointSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone> DisjointSet<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        DisjointSet {
+            index: HashMap::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+            parent: Vec::with_capacity(capacity),
+            rank: Vec::with_capacity(capacity),
+            size: Vec::with_capacity(capacity),
+            sets: 0,
+        }
+    }
+
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The number of disjoint sets.
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.index.contains_key(value)
+    }
+
+    /// Adds `value` as a set of its own. Returns `false` if it was already present.
+    pub fn add(&mut self, value: T) -> bool {
+        if self.index.contains_key(&value) {
+            return false;
+        }
+        self.insert(value);
+        true
+    }
+
+    /// The representative of the set containing `value`, or `None` if it is not present.
+    pub fn find(&mut self, value: &T) -> Option<&T> {
+        let i = *self.index.get(value)?;
+        let root = self.root(i);
+        Some(&self.values[root])
+    }
+
+    /// Merges the sets containing `a` and `b`, adding either if it is not present.
+    /// Returns `false` if they were already in the same set.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let a = self.index_of(a);
+        let b = self.index_of(b);
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return false;
+        }
+        let (parent, child) = if self.rank[a] >= self.rank[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[child] = parent;
+        self.size[parent] += self.size[child];
+        if self.rank[parent] == self.rank[child] {
+            self.rank[parent] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    /// Whether `a` and `b` are both present and in the same set.
+    pub fn connected(&mut self, a: &T, b: &T) -> bool {
+        match (self.index.get(a), self.index.get(b)) {
+            (Some(&a), Some(&b)) => self.root(a) == self.root(b),
+            _ => false,
+        }
+    }
+
+    /// The number of values in the set containing `value`.
+    pub fn set_size(&mut self, value: &T) -> Option<usize> {
+        let i = *self.index.get(value)?;
+        let root = self.root(i);
+        Some(self.size[root])
+    }
+
+    /// Every set, keyed by its representative.
+    pub fn groups(&mut self) -> HashMap<T, HashSet<T>> {
+        let mut groups: HashMap<T, HashSet<T>> = HashMap::with_capacity(self.sets);
+        for i in 0..self.len() {
+            let root = self.root(i);
+            groups
+                .entry(self.values[root].clone())
+                .or_default()
+                .insert(self.values[i].clone());
+        }
+        groups
+    }
+
+    fn index_of(&mut self, value: T) -> usize {
+        match self.index.get(&value) {
+            Some(&i) => i,
+            None => self.insert(value),
+        }
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        let i = self.values.len();
+        self.index.insert(value.clone(), i);
+        self.values.push(value);
+        self.parent.push(i);
+        self.rank.push(0);
+        self.size.push(1);
+        self.sets += 1;
+        i
+    }
+
+    fn root(&mut self, mut i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        while self.parent[i] != root {
+            let next = self.parent[i];
+            self.parent[i] = root;
+            i = next;
+        }
+        root
+    }
+}
+
+impl<T: Hash + Eq + Clone> Extend<(T, T)> for DisjointSet<T> {
+    fn extend<I: IntoIterator<Item = (T, T)>>(&mut self, pairs: I) {
+        for (a, b) in pairs {
+            self.union(a, b);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<(T, T)> for DisjointSet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(pairs: I) -> Self {
+        let mut set = DisjointSet::new();
+        set.extend(pairs);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find_connected() {
+        let mut set: DisjointSet<u32> = [(1, 2), (3, 4), (2, 4)].into_iter().collect();
+        assert!(set.add(5));
+        assert!(!set.add(1));
+        assert_eq!((set.len(), set.set_count()), (5, 2));
+
+        assert!(set.connected(&1, &3));
+        assert!(!set.connected(&1, &5));
+        assert!(!set.connected(&1, &6));
+        assert_eq!(set.find(&1).copied(), set.find(&4).copied());
+        assert_eq!(set.find(&6), None);
+        assert!(!set.union(3, 2));
+        assert!(set.union(5, 6));
+        assert_eq!(set.set_size(&6), Some(2));
+        assert_eq!(set.set_count(), 2);
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut set = DisjointSet::new();
+        set.extend([("a", "b"), ("c", "d"), ("b", "e")]);
+        set.add("f");
+        let mut groups: Vec<Vec<&str>> = set
+            .groups()
+            .into_iter()
+            .map(|(root, members)| {
+                assert!(members.contains(root));
+                let mut members: Vec<_> = members.into_iter().collect();
+                members.sort();
+                members
+            })
+            .collect();
+        groups.sort();
+        assert_eq!(groups, [vec!["a", "b", "e"], vec!["c", "d"], vec!["f"]]);
+    }
+
+    #[test]
+    fn test_long_chain_is_compressed() {
+        let n = 100_000;
+        let mut set = DisjointSet::with_capacity(n);
+        for i in 1..n {
+            set.union(i - 1, i);
+        }
+        assert_eq!(set.set_count(), 1);
+        let root = *set.find(&(n - 1)).unwrap();
+        for i in 0..n {
+            set.find(&i);
+        }
+        let root_index = set.index[&root];
+        assert!(set.parent.iter().all(|&p| p == root_index));
+        // Union by rank keeps the tree logarithmically shallow even before compression.
+        assert!(set.rank[root_index] as usize <= (n as f64).log2() as usize);
+    }
+}
@@ -0,0 +1,613 @@
+//! HyperLogLog: estimating the number of distinct values in a few kilobytes, where a
+//! `HashSet` would grow with the values themselves.
+//!
+//! Each value is hashed to 64 bits. The top `precision` bits pick one of `2^precision`
+//! registers, and the register keeps the longest run of leading zeros seen in the rest of
+//! the hash. The harmonic mean of the registers estimates the cardinality with a relative
+//! standard error of about `1.04 / sqrt(2^precision)`: 1.6% at the default precision 12,
+//! which takes 4 KiB.
+//!
+//! A sketch starts out sparse, storing only the registers that are set, and switches to a
+//! dense array of every register once that is smaller. With
+//! [`with_exact_threshold`](HyperLogLog::with_exact_threshold) it first keeps the hashes
+//! themselves in a `HashSet`, so small counts are exact until the threshold is passed.
+//!
+//! Sketches hash with a fixed function rather than `RandomState`, and integers are fed to it
+//! as little-endian bytes of a fixed width, so sketches built in different processes, even
+//! on different targets, can be merged and serialised with
+//! [`to_bytes`](HyperLogLog::to_bytes). The bytes a value's `Hash` impl writes are up to
+//! that impl, though: the standard library does not promise they stay the same across Rust
+//! versions, so sketches of `str`s or tuples, say, are best merged between builds of the
+//! same toolchain.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashset::HyperLogLog;
+//!
+//! let mut monday = HyperLogLog::new(12);
+//! let mut tuesday = HyperLogLog::new(12);
+//! for user in 0..10_000 {
+//!     monday.insert(&user);
+//!     tuesday.insert(&(user + 5_000));
+//! }
+//! monday.merge(&tuesday).unwrap();
+//!
+//! let estimate = monday.estimate();
+//! assert!((estimate - 15_000.0).abs() < 15_000.0 * 0.05);
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+pub const MIN_PRECISION: u8 = 4;
+pub const MAX_PRECISION: u8 = 18;
+pub const DEFAULT_PRECISION: u8 = 12;
+
+const MAGIC: &[u8; 3] = b"HLL";
+const FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HllError {
+    /// Sketches of different precisions cannot be merged.
+    PrecisionMismatch { left: u8, right: u8 },
+    /// The bytes are not a serialised sketch.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for HllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HllError::PrecisionMismatch { left, right } => {
+                write!(
+                    f,
+                    "cannot merge precision {} with precision {}",
+                    left, right
+                )
+            }
+            HllError::Corrupt(reason) => write!(f, "corrupt sketch: {}", reason),
+        }
+    }
+}
+
+impl Error for HllError {}
+
+/// FNV-1a with the murmur3 finalizer, so hashes are the same in every process.
+///
+/// The default `write_*` methods write native-endian bytes and `usize` at the pointer
+/// width, so they are overridden to hash the same on every target.
+struct SketchHasher(u64);
+
+macro_rules! write_le {
+    ($($method:ident: $t:ty),*) => {
+        $(
+            fn $method(&mut self, n: $t) {
+                self.write(&n.to_le_bytes());
+            }
+        )*
+    };
+}
+
+impl Hasher for SketchHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    write_le!(
+        write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+        write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128
+    );
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_isize(&mut self, n: isize) {
+        self.write_i64(n as i64);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut h = self.0;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xff51afd7ed558ccd);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+        h ^ (h >> 33)
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = SketchHasher(0xcbf29ce484222325);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Repr {
+    Exact(HashSet<u64>),
+    Sparse(BTreeMap<u32, u8>),
+    Dense(Vec<u8>),
+}
+
+/// Which of the three representations a sketch is using.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    Exact,
+    Sparse,
+    Dense,
+}
+
+/// A HyperLogLog sketch of the distinct values of type `T`.
+pub struct HyperLogLog<T: ?Sized> {
+    precision: u8,
+    exact_threshold: usize,
+    repr: Repr,
+    _values: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> Clone for HyperLogLog<T> {
+    fn clone(&self) -> Self {
+        HyperLogLog {
+            precision: self.precision,
+            exact_threshold: self.exact_threshold,
+            repr: self.repr.clone(),
+            _values: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for HyperLogLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HyperLogLog")
+            .field("precision", &self.precision)
+            .field("representation", &self.representation())
+            .field("estimate", &self.estimate())
+            .finish()
+    }
+}
+
+impl<T: Hash + ?Sized> Default for HyperLogLog<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRECISION)
+    }
+}
+
+impl<T: Hash + ?Sized> HyperLogLog<T> {
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 18.
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "precision must be between {} and {}",
+            MIN_PRECISION,
+            MAX_PRECISION
+        );
+        HyperLogLog {
+            precision,
+            exact_threshold: 0,
+            repr: Repr::Sparse(BTreeMap::new()),
+            _values: PhantomData,
+        }
+    }
+
+    /// Counts exactly, by keeping every hash in a `HashSet`, until more than `threshold`
+    /// distinct values have been seen.
+    ///
+    /// Only has an effect on an empty sketch.
+    pub fn with_exact_threshold(mut self, threshold: usize) -> Self {
+        self.exact_threshold = threshold;
+        if self.is_empty() && threshold > 0 {
+            self.repr = Repr::Exact(HashSet::new());
+        }
+        self
+    }
+
+    pub fn insert(&mut self, value: &T) {
+        self.insert_hash(hash(value));
+    }
+
+    /// Adds every value of `values`.
+    pub fn extend<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        for value in values {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: ?Sized> HyperLogLog<T> {
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn representation(&self) -> Representation {
+        match self.repr {
+            Repr::Exact(_) => Representation::Exact,
+            Repr::Sparse(_) => Representation::Sparse,
+            Repr::Dense(_) => Representation::Dense,
+        }
+    }
+
+    /// The relative standard error of [`estimate`](Self::estimate) once counting is no
+    /// longer exact.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers() as f64).sqrt()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match &self.repr {
+            Repr::Exact(hashes) => hashes.is_empty(),
+            Repr::Sparse(registers) => registers.is_empty(),
+            Repr::Dense(registers) => registers.iter().all(|&r| r == 0),
+        }
+    }
+
+    /// The estimated number of distinct values inserted.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers() as f64;
+        let (sum, zeros) = match &self.repr {
+            Repr::Exact(hashes) => return hashes.len() as f64,
+            Repr::Sparse(registers) => {
+                let zeros = self.registers() - registers.len();
+                let set: f64 = registers.values().map(|&r| 0.5f64.powi(r as i32)).sum();
+                (set + zeros as f64, zeros)
+            }
+            Repr::Dense(registers) => {
+                let sum = registers.iter().map(|&r| 0.5f64.powi(r as i32)).sum();
+                (sum, registers.iter().filter(|&&r| r == 0).count())
+            }
+        };
+        let alpha = match self.registers() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let raw = alpha * m * m / sum;
+        // Linear counting is more accurate while many registers are still empty. With
+        // 64-bit hashes no large-range correction is needed.
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// Adds the values of `other` to this sketch, so it estimates the size of the union.
+    pub fn merge(&mut self, other: &HyperLogLog<T>) -> Result<(), HllError> {
+        if self.precision != other.precision {
+            return Err(HllError::PrecisionMismatch {
+                left: self.precision,
+                right: other.precision,
+            });
+        }
+        match &other.repr {
+            Repr::Exact(hashes) => {
+                for &hash in hashes {
+                    self.insert_hash(hash);
+                }
+            }
+            Repr::Sparse(registers) => {
+                for (&index, &rank) in registers {
+                    self.set_register(index, rank);
+                }
+            }
+            Repr::Dense(registers) => {
+                self.densify();
+                for (index, &rank) in registers.iter().enumerate() {
+                    self.set_register(index as u32, rank);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialises the sketch: a header with the precision and exact threshold, then the
+    /// hashes, the set registers or all registers, depending on the representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        bytes.push(self.precision);
+        bytes.extend_from_slice(&(self.exact_threshold as u64).to_le_bytes());
+        match &self.repr {
+            Repr::Exact(hashes) => {
+                bytes.push(0);
+                bytes.extend_from_slice(&(hashes.len() as u32).to_le_bytes());
+                for hash in hashes {
+                    bytes.extend_from_slice(&hash.to_le_bytes());
+                }
+            }
+            Repr::Sparse(registers) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(registers.len() as u32).to_le_bytes());
+                for (index, rank) in registers {
+                    bytes.extend_from_slice(&index.to_le_bytes());
+                    bytes.push(*rank);
+                }
+            }
+            Repr::Dense(registers) => {
+                bytes.push(2);
+                bytes.extend_from_slice(registers);
+            }
+        }
+        bytes
+    }
+
+    /// Reads a sketch written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HllError> {
+        let mut reader = Reader(bytes);
+        if reader.take(3)? != MAGIC {
+            return Err(HllError::Corrupt("bad magic"));
+        }
+        if reader.u8()? != FORMAT_VERSION {
+            return Err(HllError::Corrupt("unsupported version"));
+        }
+        let precision = reader.u8()?;
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(HllError::Corrupt("precision out of range"));
+        }
+        let exact_threshold = u64::from_le_bytes(reader.array()?) as usize;
+        let registers = 1usize << precision;
+        let max_rank = 65 - precision;
+        let repr = match reader.u8()? {
+            0 => {
+                let len = u32::from_le_bytes(reader.array()?);
+                let mut hashes = HashSet::new();
+                for _ in 0..len {
+                    hashes.insert(u64::from_le_bytes(reader.array()?));
+                }
+                Repr::Exact(hashes)
+            }
+            1 => {
+                let len = u32::from_le_bytes(reader.array()?);
+                let mut sparse = BTreeMap::new();
+                for _ in 0..len {
+                    let index = u32::from_le_bytes(reader.array()?);
+                    let rank = reader.u8()?;
+                    if index as usize >= registers || rank == 0 || rank > max_rank {
+                        return Err(HllError::Corrupt("register out of range"));
+                    }
+                    sparse.insert(index, rank);
+                }
+                Repr::Sparse(sparse)
+            }
+            2 => {
+                let dense = reader.take(registers)?.to_vec();
+                if dense.iter().any(|&rank| rank > max_rank) {
+                    return Err(HllError::Corrupt("register out of range"));
+                }
+                Repr::Dense(dense)
+            }
+            _ => return Err(HllError::Corrupt("unknown representation")),
+        };
+        if !reader.0.is_empty() {
+            return Err(HllError::Corrupt("trailing bytes"));
+        }
+        Ok(HyperLogLog {
+            precision,
+            exact_threshold,
+            repr,
+            _values: PhantomData,
+        })
+    }
+
+    fn registers(&self) -> usize {
+        1 << self.precision
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        if let Repr::Exact(hashes) = &mut self.repr {
+            hashes.insert(hash);
+            if hashes.len() > self.exact_threshold {
+                self.sparsify();
+            }
+            return;
+        }
+        let p = self.precision as u32;
+        let index = (hash >> (64 - p)) as u32;
+        // The sentinel bit caps the rank at 64 - p + 1 when the remaining bits are all zero.
+        let rank = ((hash << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+        self.set_register(index, rank);
+    }
+
+    fn set_register(&mut self, index: u32, rank: u8) {
+        if rank == 0 {
+            return;
+        }
+        if let Repr::Exact(_) = self.repr {
+            self.sparsify();
+        }
+        match &mut self.repr {
+            Repr::Sparse(registers) => {
+                let register = registers.entry(index).or_insert(0);
+                *register = (*register).max(rank);
+                // Each sparse register costs an index and a rank against one byte dense.
+                if registers.len() * 5 > self.registers() {
+                    self.densify();
+                }
+            }
+            Repr::Dense(registers) => {
+                let register = &mut registers[index as usize];
+                *register = (*register).max(rank);
+            }
+            Repr::Exact(_) => unreachable!(),
+        }
+    }
+
+    fn sparsify(&mut self) {
+        if let Repr::Exact(hashes) = &mut self.repr {
+            let hashes = std::mem::take(hashes);
+            self.repr = Repr::Sparse(BTreeMap::new());
+            for hash in hashes {
+                self.insert_hash(hash);
+            }
+        }
+    }
+
+    fn densify(&mut self) {
+        self.sparsify();
+        if let Repr::Sparse(sparse) = &self.repr {
+            let mut dense = vec![0; self.registers()];
+            for (&index, &rank) in sparse {
+                dense[index as usize] = rank;
+            }
+            self.repr = Repr::Dense(dense);
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], HllError> {
+        if self.0.len() < n {
+            return Err(HllError::Corrupt("truncated"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, HllError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], HllError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch(precision: u8, values: std::ops::Range<u64>) -> HyperLogLog<u64> {
+        let mut hll = HyperLogLog::new(precision);
+        hll.extend(values.collect::<Vec<_>>().iter());
+        hll
+    }
+
+    #[test]
+    fn test_estimate_within_error_bounds() {
+        for precision in [10, 12, 14] {
+            for n in [100u64, 1_000, 10_000, 100_000] {
+                let exact: HashSet<u64> = (0..n).map(|i| i * 7919).collect();
+                let mut hll = HyperLogLog::new(precision);
+                for value in &exact {
+                    hll.insert(value);
+                    hll.insert(value);
+                }
+                let error = (hll.estimate() - exact.len() as f64).abs() / exact.len() as f64;
+                // Three standard errors; the hash is fixed, so this is deterministic.
+                assert!(
+                    error < 3.0 * hll.standard_error(),
+                    "precision {} n {}: error {}",
+                    precision,
+                    n,
+                    error
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sparse_switches_to_dense() {
+        let mut hll = sketch(10, 0..50);
+        assert_eq!(hll.representation(), Representation::Sparse);
+        hll.extend((50..1_000u64).collect::<Vec<_>>().iter());
+        assert_eq!(hll.representation(), Representation::Dense);
+        assert!(HyperLogLog::<str>::new(10).is_empty());
+    }
+
+    #[test]
+    fn test_exact_below_threshold() {
+        let mut hll = HyperLogLog::<str>::new(8).with_exact_threshold(100);
+        for i in 0..100 {
+            hll.insert(&format!("user-{}", i % 100));
+            hll.insert(&format!("user-{}", i % 100));
+        }
+        assert_eq!(hll.representation(), Representation::Exact);
+        assert_eq!(hll.estimate(), 100.0);
+
+        hll.insert("user-100");
+        assert_ne!(hll.representation(), Representation::Exact);
+        assert!((hll.estimate() - 101.0).abs() < 101.0 * 3.0 * hll.standard_error());
+    }
+
+    #[test]
+    fn test_merge_estimates_union() {
+        let mut left = sketch(12, 0..40_000);
+        let right = sketch(12, 20_000..60_000);
+        let sparse = sketch(12, 100_000..100_010);
+        let exact = {
+            let mut hll = HyperLogLog::new(12).with_exact_threshold(10);
+            hll.extend([1_000_000u64, 1].iter());
+            hll
+        };
+        left.merge(&right).unwrap();
+        left.merge(&sparse).unwrap();
+        left.merge(&exact).unwrap();
+        let error = (left.estimate() - 60_011.0).abs() / 60_011.0;
+        assert!(error < 3.0 * left.standard_error(), "error {}", error);
+
+        // Merging into a sparse sketch gives the same registers as inserting directly.
+        let mut merged = sketch(12, 0..10);
+        merged.merge(&sketch(12, 10..5_000)).unwrap();
+        assert_eq!(merged.repr, sketch(12, 0..5_000).repr);
+
+        assert_eq!(
+            left.merge(&sketch(10, 0..1)),
+            Err(HllError::PrecisionMismatch {
+                left: 12,
+                right: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_integers_hash_as_fixed_width_little_endian() {
+        fn hash_bytes(bytes: &[u8]) -> u64 {
+            let mut hasher = SketchHasher(0xcbf29ce484222325);
+            hasher.write(bytes);
+            hasher.finish()
+        }
+        assert_eq!(hash(&0x0102_0304u32), hash_bytes(&[4, 3, 2, 1]));
+        assert_eq!(hash(&-2i16), hash_bytes(&[0xfe, 0xff]));
+        assert_eq!(hash(&7usize), hash(&7u64));
+        assert_eq!(hash(&-7isize), hash(&-7i64));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let exact = {
+            let mut hll = HyperLogLog::new(6).with_exact_threshold(10);
+            hll.extend([3u64, 5, 8].iter());
+            hll
+        };
+        for hll in [exact, sketch(14, 0..100), sketch(6, 0..1_000)] {
+            let bytes = hll.to_bytes();
+            let read = HyperLogLog::<u64>::from_bytes(&bytes).unwrap();
+            assert_eq!(read.repr, hll.repr);
+            assert_eq!(read.precision(), hll.precision());
+            assert_eq!(read.estimate(), hll.estimate());
+            assert_eq!(read.exact_threshold, hll.exact_threshold);
+
+            assert!(HyperLogLog::<u64>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        }
+        assert_eq!(
+            HyperLogLog::<u64>::from_bytes(b"HLX").unwrap_err(),
+            HllError::Corrupt("bad magic")
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Set-based analytics built on `std::collections::HashSet`.
+
+pub mod disjoint_set;
+pub mod hyperloglog;
+pub mod similarity;
+
+pub use disjoint_set::DisjointSet;
+pub use hyperloglog::{HllError, HyperLogLog, Representation};
+pub use similarity::{diff_report, DiffReport, Overlap, SetComparison};
@@ -56,6 +56,7 @@ mod tests {
         main();
     }
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_hashset_remove() {
         let mut set: std::collections::HashSet<i32> = HashSet::new();
         set.insert(1);
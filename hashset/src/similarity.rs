@@ -0,0 +1,305 @@
+//! Similarity scores and diffs between two collections of values, such as the values of
+//! a column in two versions of a dataset.
+//!
+//! Every entry point takes two `IntoIterator`s, so a `HashSet`, a `Vec` with duplicates
+//! or a lazy iterator over rows all work. Both sides are folded into a single map from
+//! each distinct value to its number of occurrences on either side, so values common to
+//! both are only stored once and neither side has to be collected into a set first.
+//!
+//! Scores are over distinct values. When a denominator is zero the smaller set is empty,
+//! which makes it trivially a subset of the other, so the score is 1.0.
+//!
+//! # Examples
+//!
+//! ```
+//! use hashset::similarity::{diff_report, jaccard};
+//!
+//! let old = ["KE", "UG", "TZ", "KE"];
+//! let new = vec!["KE", "TZ", "RW"];
+//! assert_eq!(jaccard(old, new.iter().copied()), 0.5);
+//!
+//! let report = diff_report(old, new);
+//! assert_eq!(report.added, [("RW", 1)]);
+//! assert_eq!(report.removed, [("UG", 1)]);
+//! assert_eq!(report.retained.len(), 2);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// How many times a value occurs on each side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub old: usize,
+    pub new: usize,
+}
+
+/// The sizes of two sets of distinct values and of their intersection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overlap {
+    pub old: usize,
+    pub new: usize,
+    pub intersection: usize,
+}
+
+impl Overlap {
+    /// Compares the distinct values of `old` and `new`.
+    pub fn of<T, I, J>(old: I, new: J) -> Overlap
+    where
+        T: Hash + Eq,
+        I: IntoIterator<Item = T>,
+        J: IntoIterator<Item = T>,
+    {
+        SetComparison::new(old, new).overlap()
+    }
+
+    pub fn union(&self) -> usize {
+        self.old + self.new - self.intersection
+    }
+
+    /// `|A ∩ B| / |A ∪ B|`.
+    pub fn jaccard(&self) -> f64 {
+        ratio(self.intersection, self.union())
+    }
+
+    /// `2|A ∩ B| / (|A| + |B|)`, the Sørensen–Dice coefficient.
+    pub fn dice(&self) -> f64 {
+        ratio(2 * self.intersection, self.old + self.new)
+    }
+
+    /// `|A ∩ B| / min(|A|, |B|)`: 1.0 when either set contains the other.
+    pub fn overlap_coefficient(&self) -> f64 {
+        ratio(self.intersection, self.old.min(self.new))
+    }
+
+    /// `|A ∩ B| / |A|`: the fraction of old values still present in new.
+    pub fn containment(&self) -> f64 {
+        ratio(self.intersection, self.old)
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        1.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// The occurrence counts of every value on either side of a comparison.
+#[derive(Debug, Clone)]
+pub struct SetComparison<T> {
+    counts: HashMap<T, Counts>,
+}
+
+impl<T: Hash + Eq> SetComparison<T> {
+    pub fn new<I, J>(old: I, new: J) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        J: IntoIterator<Item = T>,
+    {
+        let mut counts: HashMap<T, Counts> = HashMap::new();
+        for value in old {
+            counts.entry(value).or_default().old += 1;
+        }
+        for value in new {
+            counts.entry(value).or_default().new += 1;
+        }
+        SetComparison { counts }
+    }
+
+    pub fn counts(&self, value: &T) -> Counts {
+        self.counts.get(value).copied().unwrap_or_default()
+    }
+
+    pub fn overlap(&self) -> Overlap {
+        let mut overlap = Overlap::default();
+        for counts in self.counts.values() {
+            overlap.old += (counts.old > 0) as usize;
+            overlap.new += (counts.new > 0) as usize;
+            overlap.intersection += (counts.old > 0 && counts.new > 0) as usize;
+        }
+        overlap
+    }
+
+    pub fn diff_report(self) -> DiffReport<T> {
+        let mut report = DiffReport {
+            added: Vec::new(),
+            removed: Vec::new(),
+            retained: Vec::new(),
+        };
+        for (value, counts) in self.counts {
+            match (counts.old, counts.new) {
+                (0, new) => report.added.push((value, new)),
+                (old, 0) => report.removed.push((value, old)),
+                _ => report.retained.push((value, counts)),
+            }
+        }
+        report.added.sort_by_key(|&(_, n)| Reverse(n));
+        report.removed.sort_by_key(|&(_, n)| Reverse(n));
+        report
+            .retained
+            .sort_by_key(|&(_, counts)| Reverse(counts.old + counts.new));
+        report
+    }
+}
+
+/// The values only in new, only in old, and in both, each with its occurrence counts.
+///
+/// Each list is sorted by descending count; the order of values with equal counts is
+/// unspecified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffReport<T> {
+    pub added: Vec<(T, usize)>,
+    pub removed: Vec<(T, usize)>,
+    pub retained: Vec<(T, Counts)>,
+}
+
+impl<T> DiffReport<T> {
+    pub fn overlap(&self) -> Overlap {
+        Overlap {
+            old: self.removed.len() + self.retained.len(),
+            new: self.added.len() + self.retained.len(),
+            intersection: self.retained.len(),
+        }
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<T> fmt::Display for DiffReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = |values: &[(T, usize)]| values.iter().map(|(_, n)| n).sum::<usize>();
+        write!(
+            f,
+            "{} added ({} rows), {} removed ({} rows), {} retained, jaccard {:.3}",
+            self.added.len(),
+            rows(&self.added),
+            self.removed.len(),
+            rows(&self.removed),
+            self.retained.len(),
+            self.overlap().jaccard()
+        )
+    }
+}
+
+/// The Jaccard similarity of the distinct values of `old` and `new`.
+pub fn jaccard<T, I, J>(old: I, new: J) -> f64
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+{
+    Overlap::of(old, new).jaccard()
+}
+
+/// The Sørensen–Dice coefficient of the distinct values of `old` and `new`.
+pub fn dice<T, I, J>(old: I, new: J) -> f64
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+{
+    Overlap::of(old, new).dice()
+}
+
+/// The overlap coefficient of the distinct values of `old` and `new`.
+pub fn overlap_coefficient<T, I, J>(old: I, new: J) -> f64
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+{
+    Overlap::of(old, new).overlap_coefficient()
+}
+
+/// The fraction of distinct values of `old` that also occur in `new`.
+pub fn containment<T, I, J>(old: I, new: J) -> f64
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+{
+    Overlap::of(old, new).containment()
+}
+
+/// Lists the values added, removed and retained between `old` and `new`.
+pub fn diff_report<T, I, J>(old: I, new: J) -> DiffReport<T>
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+    J: IntoIterator<Item = T>,
+{
+    SetComparison::new(old, new).diff_report()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_scores() {
+        let a: HashSet<u32> = (0..10).collect();
+        let b: HashSet<u32> = (5..20).collect();
+        let overlap = Overlap::of(&a, &b);
+        assert_eq!(
+            overlap,
+            Overlap {
+                old: 10,
+                new: 15,
+                intersection: 5
+            }
+        );
+        assert_eq!(overlap.union(), 20);
+        assert_eq!(jaccard(&a, &b), 0.25);
+        assert_eq!(dice(&a, &b), 0.4);
+        assert_eq!(overlap_coefficient(&a, &b), 0.5);
+        assert_eq!(containment(&a, &b), 0.5);
+        assert_eq!(containment(&b, &a), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn test_duplicates_and_lazy_iterators() {
+        let old = "a b b c c c".split(' ');
+        let new = (0..4).map(|i| ["b", "c", "d", "d"][i]);
+        assert_eq!(jaccard(old.clone(), new.clone()), 0.5);
+        assert_eq!(overlap_coefficient(old, new), 2.0 / 3.0);
+        assert_eq!(overlap_coefficient(0..3, 0..10), 1.0);
+    }
+
+    #[test]
+    fn test_empty_sides() {
+        let empty: [u8; 0] = [];
+        assert_eq!(jaccard(empty, empty), 1.0);
+        assert_eq!(dice(empty, [1]), 0.0);
+        assert_eq!(containment(empty, [1]), 1.0);
+        assert_eq!(containment([1], empty), 0.0);
+        assert!(diff_report(empty, empty).is_unchanged());
+    }
+
+    #[test]
+    fn test_diff_report_counts() {
+        let old = vec!["x", "y", "y", "z"];
+        let new = vec!["y", "z", "z", "z", "w", "v", "v"];
+        let report = diff_report(old, new);
+        assert_eq!(report.added, [("v", 2), ("w", 1)]);
+        assert_eq!(report.removed, [("x", 1)]);
+        assert_eq!(
+            report.retained,
+            [
+                ("z", Counts { old: 1, new: 3 }),
+                ("y", Counts { old: 2, new: 1 })
+            ]
+        );
+        assert!(!report.is_unchanged());
+        assert_eq!(
+            report.to_string(),
+            "2 added (3 rows), 1 removed (1 rows), 2 retained, jaccard 0.400"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Test-case files.
+//!
+//! A file holds the cases for one exercise, in TOML or JSON depending on its extension:
+//!
+//! ```toml
+//! exercise = "two_sum"
+//! time_limit_ms = 500        # optional default for the cases below
+//!
+//! [[cases]]
+//! name = "example"
+//! input = { nums = [2, 7, 11, 15], target = 9 }
+//! expected = [2, 7]
+//! time_limit_ms = 50         # optional
+//! ```
+//!
+//! TOML has no null, so cases expecting `None` have to go in a JSON file.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The time limit of cases that set none, nor their file.
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum CaseError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for CaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaseError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            CaseError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            CaseError::UnknownFormat(path) => {
+                write!(f, "{}: expected a .json or .toml file", path.display())
+            }
+        }
+    }
+}
+
+impl Error for CaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CaseError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub name: String,
+    pub input: Value,
+    pub expected: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaseFile {
+    pub exercise: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit_ms: Option<u64>,
+    #[serde(default)]
+    pub cases: Vec<Case>,
+}
+
+impl CaseFile {
+    /// Reads a `.json` or `.toml` case file.
+    pub fn load(path: &Path) -> Result<CaseFile, CaseError> {
+        let format = Format::of(path).ok_or_else(|| CaseError::UnknownFormat(path.into()))?;
+        let text = fs::read_to_string(path).map_err(|e| CaseError::Io(path.into(), e))?;
+        let parsed = match format {
+            Format::Json => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(&text).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|e| CaseError::Parse(path.into(), e))
+    }
+
+    /// Writes the file as JSON or TOML depending on the extension of `path`.
+    pub fn save(&self, path: &Path) -> Result<(), CaseError> {
+        let format = Format::of(path).ok_or_else(|| CaseError::UnknownFormat(path.into()))?;
+        let text = match format {
+            Format::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string(self).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| CaseError::Parse(path.into(), e))?;
+        fs::write(path, text).map_err(|e| CaseError::Io(path.into(), e))
+    }
+
+    /// The time limit of `case`: its own, else the file's, else [`DEFAULT_TIME_LIMIT`].
+    pub fn time_limit(&self, case: &Case) -> Duration {
+        case.time_limit_ms
+            .or(self.time_limit_ms)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_TIME_LIMIT)
+    }
+}
+
+enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// The case files under `path`, in sorted order, or `path` itself if it is a file.
+pub fn discover(path: &Path) -> Result<Vec<PathBuf>, CaseError> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        walk(path, &mut files)?;
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), CaseError> {
+    let entries = fs::read_dir(dir).map_err(|e| CaseError::Io(dir.into(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| CaseError::Io(dir.into(), e))?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if Format::of(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_and_toml_agree() {
+        let dir = std::env::temp_dir().join(format!("turing-cases-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let file = CaseFile {
+            exercise: "two_sum".into(),
+            time_limit_ms: Some(20),
+            cases: vec![Case {
+                name: "example".into(),
+                input: json!({"nums": [2, 7], "target": 9}),
+                expected: json!([2, 7]),
+                time_limit_ms: None,
+            }],
+        };
+        file.save(&dir.join("a.json")).unwrap();
+        file.save(&dir.join("nested/b.toml")).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let found = discover(&dir).unwrap();
+        assert_eq!(found, [dir.join("a.json"), dir.join("nested/b.toml")]);
+        for path in found {
+            let loaded = CaseFile::load(&path).unwrap();
+            assert_eq!(loaded, file);
+            assert_eq!(
+                loaded.time_limit(&loaded.cases[0]),
+                Duration::from_millis(20)
+            );
+        }
+        assert!(matches!(
+            CaseFile::load(&dir.join("notes.txt")),
+            Err(CaseError::UnknownFormat(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fixture_files_parse() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("cases");
+        for path in discover(&dir).unwrap() {
+            let file = CaseFile::load(&path).unwrap();
+            assert!(
+                crate::exercise::find(&file.exercise).is_some(),
+                "{}",
+                path.display()
+            );
+        }
+    }
+}
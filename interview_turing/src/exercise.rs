@@ -0,0 +1,150 @@
+//! The exercises the runner knows about, each wrapping an algorithm from one of the
+//! sibling crates behind a JSON-in, JSON-out interface.
+//!
+//! Test-case inputs are objects whose fields name the function's arguments, e.g.
+//! `{ "nums": [2, 7, 11, 15], "target": 9 }` for `two_sum`, and outputs are the function's
+//! return value serialised as JSON: tuples become arrays and `None` becomes `null`.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// An algorithm that can be run against test cases.
+#[derive(Debug, Clone, Copy)]
+pub struct Exercise {
+    /// The name test-case files refer to the exercise by.
+    pub name: &'static str,
+    /// The crate the implementation lives in.
+    pub source: &'static str,
+    pub description: &'static str,
+    run: fn(Value) -> Result<Value, String>,
+}
+
+impl Exercise {
+    /// An exercise backed by `run`, for trying out implementations that are not
+    /// registered in [`exercises`].
+    pub fn new(
+        name: &'static str,
+        source: &'static str,
+        description: &'static str,
+        run: fn(Value) -> Result<Value, String>,
+    ) -> Self {
+        Exercise {
+            name,
+            source,
+            description,
+            run,
+        }
+    }
+
+    /// Runs the exercise on `input`. Fails if `input` does not have the argument fields the
+    /// exercise expects.
+    pub fn run(&self, input: Value) -> Result<Value, String> {
+        (self.run)(input)
+    }
+}
+
+/// Deserialises the arguments, calls `f` and serialises its result.
+pub(crate) fn call<I, O>(input: Value, f: impl FnOnce(I) -> O) -> Result<Value, String>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+{
+    let input = serde_json::from_value(input).map_err(|e| format!("invalid input: {}", e))?;
+    serde_json::to_value(f(input)).map_err(|e| format!("invalid output: {}", e))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Nums {
+    pub nums: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NumsTarget {
+    pub nums: Vec<i32>,
+    pub target: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Intervals {
+    pub intervals: Vec<(i32, i32)>,
+}
+
+/// Every exercise, in the order `list` shows them.
+pub fn exercises() -> Vec<Exercise> {
+    vec![
+        Exercise {
+            name: "two_sum",
+            source: "two_sum",
+            description: "the pair of numbers in `nums` adding up to `target`, if any",
+            run: |input| call(input, |i: NumsTarget| two_sum::two_sum(i.nums, i.target)),
+        },
+        Exercise {
+            name: "max_subarray",
+            source: "kadane_max_subarray",
+            description: "the largest sum of a contiguous subarray, with its start and end",
+            run: |input| call(input, |i: Nums| kadane_max_subarray::max_subarray(&i.nums)),
+        },
+        Exercise {
+            name: "merge_intervals",
+            source: "merge_intervals",
+            description: "the union of a list of intervals, as sorted disjoint intervals",
+            run: |input| {
+                call(input, |i: Intervals| {
+                    merge_intervals::merge_intervals(i.intervals)
+                })
+            },
+        },
+        Exercise {
+            name: "length_of_lis",
+            source: "longest_increasing_subsequence",
+            description: "the length of the longest strictly increasing subsequence",
+            run: |input| {
+                call(input, |i: Nums| {
+                    longest_increasing_subsequence::length_of_lis(i.nums)
+                })
+            },
+        },
+    ]
+}
+
+/// The exercise called `name`.
+pub fn find(name: &str) -> Option<Exercise> {
+    exercises().into_iter().find(|e| e.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_run_converts_arguments_and_results() {
+        let two_sum = find("two_sum").unwrap();
+        assert_eq!(
+            two_sum.run(json!({"nums": [2, 7, 11, 15], "target": 9})),
+            Ok(json!([2, 7]))
+        );
+        assert_eq!(
+            two_sum.run(json!({"nums": [1], "target": 9})),
+            Ok(Value::Null)
+        );
+        assert_eq!(
+            find("merge_intervals")
+                .unwrap()
+                .run(json!({"intervals": [[1, 3], [2, 6]]})),
+            Ok(json!([[1, 6]]))
+        );
+    }
+
+    #[test]
+    fn test_run_rejects_bad_input() {
+        let lis = find("length_of_lis").unwrap();
+        assert!(lis.run(json!({"nums": "1 2 3"})).is_err());
+        assert!(lis.run(json!({"nums": [1], "extra": 1})).is_err());
+        assert!(find("fizz_buzz").is_none());
+    }
+}
@@ -0,0 +1,13 @@
+//! A runner for the algorithm exercises in the sibling crates: it runs each exercise
+//! against test-case files of inputs and expected outputs and reports what passed,
+//! and stress-tests them against brute-force oracles on random inputs.
+
+pub mod cases;
+pub mod exercise;
+pub mod runner;
+pub mod stress;
+
+pub use cases::{Case, CaseError, CaseFile};
+pub use exercise::{exercises, Exercise};
+pub use runner::{run_case, run_file, CaseResult, Outcome, Report};
+pub use stress::{stress, Failure, Oracle, StressConfig};
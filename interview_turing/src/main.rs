@@ -1,3 +1,143 @@
-fn main() {
-    println!("Hello, world!");
+use interview_turing::{
+    cases, exercise, exercises, run_file, stress, CaseFile, Report, StressConfig,
+};
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "\
+usage: interview_turing list
+       interview_turing run [--exercise NAME] [PATH...]
+       interview_turing stress [--seed N] [--iterations N] [--max-size N] [--save DIR] [EXERCISE...]
+
+`run` runs every .json and .toml case file under each PATH, by default the
+crate's cases/ directory, and exits non-zero if any case does not pass.
+
+`stress` checks each EXERCISE, by default all of them, against a brute-force
+oracle on random inputs. The smallest failing input found is saved as a case
+file in DIR, by default cases/regressions/, so `run` picks it up.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            for exercise in exercises() {
+                println!(
+                    "{:<16} {:<32} {}",
+                    exercise.name, exercise.source, exercise.description
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        Some("run") => run(&args[1..]),
+        Some("stress") => stress_test(&args[1..]).unwrap_or_else(usage),
+        None => run(&[]),
+        Some(_) => usage(()),
+    }
+}
+
+fn usage(_: ()) -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
+
+fn run(args: &[String]) -> ExitCode {
+    let mut only = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exercise" => match args.next() {
+                Some(name) => only = Some(name.clone()),
+                None => return usage(()),
+            },
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("cases"));
+    }
+
+    let mut report = Report::default();
+    for path in paths {
+        let files = match cases::discover(&path) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        for file in files {
+            let file = match CaseFile::load(&file) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            if only.as_ref().is_some_and(|name| *name != file.exercise) {
+                continue;
+            }
+            report.results.extend(run_file(&file));
+        }
+    }
+
+    println!("{}", report);
+    if report.failed() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Returns `Err` on a usage error.
+fn stress_test(args: &[String]) -> Result<ExitCode, ()> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut config = StressConfig::new(seed);
+    let mut save_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("cases/regressions");
+    let mut names = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut number = || args.next().and_then(|n| n.parse().ok()).ok_or(());
+        match arg.as_str() {
+            "--seed" => config.seed = number()?,
+            "--iterations" => config = config.iterations(number()? as usize),
+            "--max-size" => config = config.max_size(number()? as usize),
+            "--save" => save_dir = args.next().ok_or(())?.into(),
+            name => names.push(name.to_string()),
+        }
+    }
+    if names.is_empty() {
+        names = stress::oracles()
+            .iter()
+            .map(|o| o.exercise.to_string())
+            .collect();
+    }
+
+    let mut failed = false;
+    for name in names {
+        let (Some(exercise), Some(oracle)) = (exercise::find(&name), stress::find(&name)) else {
+            eprintln!("no exercise with an oracle called {:?}", name);
+            return Err(());
+        };
+        match stress(exercise, &oracle, &config) {
+            Ok(checked) => println!("PASS {} ({} inputs, seed {})", name, checked, config.seed),
+            Err(failure) => {
+                failed = true;
+                println!("FAIL {}", failure);
+                match failure.save(&save_dir) {
+                    Ok(path) => println!("  saved {}", path.display()),
+                    Err(e) => eprintln!("  could not save the case: {}", e),
+                }
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
 }
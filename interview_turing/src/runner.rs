@@ -0,0 +1,304 @@
+//! Runs test cases against exercises and reports the results.
+//!
+//! Each case runs on its own thread so that a time limit can be enforced and a panic
+//! reported as a failure rather than taking the runner down. A case that overruns its
+//! limit is reported as timed out and its thread is left to finish in the background.
+
+use crate::cases::{Case, CaseFile};
+use crate::exercise::{self, Exercise};
+use serde_json::Value;
+use std::any::Any;
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// The exercise returned `actual`, which differs from the expected output at `diff`.
+    Failed {
+        actual: Value,
+        diff: Vec<String>,
+    },
+    TimedOut(Duration),
+    /// The input was invalid, the exercise is unknown, or it panicked.
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub exercise: String,
+    pub case: String,
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.outcome == Outcome::Passed
+    }
+}
+
+impl fmt::Display for CaseResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self.outcome {
+            Outcome::Passed => "PASS",
+            Outcome::Failed { .. } => "FAIL",
+            Outcome::TimedOut(_) => "TIME",
+            Outcome::Error(_) => "ERR ",
+        };
+        write!(
+            f,
+            "{} {}/{} ({:.3} ms)",
+            status,
+            self.exercise,
+            self.case,
+            self.elapsed.as_secs_f64() * 1000.0
+        )?;
+        match &self.outcome {
+            Outcome::Passed => Ok(()),
+            Outcome::Failed { actual, diff } => {
+                write!(f, "\n     got {}", actual)?;
+                for line in diff {
+                    write!(f, "\n     {}", line)?;
+                }
+                Ok(())
+            }
+            Outcome::TimedOut(limit) => write!(f, "\n     exceeded {:?}", limit),
+            Outcome::Error(message) => write!(f, "\n     {}", message),
+        }
+    }
+}
+
+/// Runs `case` against `exercise`, giving up after `time_limit`.
+pub fn run_case(exercise: Exercise, case: &Case, time_limit: Duration) -> CaseResult {
+    let (tx, rx) = mpsc::channel();
+    let input = case.input.clone();
+    let start = Instant::now();
+    let handle = thread::spawn(move || {
+        let output = exercise.run(input);
+        // The runner has stopped listening if the case timed out.
+        let _ = tx.send((output, start.elapsed()));
+    });
+    let (outcome, elapsed) = match rx.recv_timeout(time_limit) {
+        Ok((Ok(_), elapsed)) if elapsed > time_limit => (Outcome::TimedOut(time_limit), elapsed),
+        Ok((Ok(actual), elapsed)) => {
+            let diff = diff(&case.expected, &actual);
+            if diff.is_empty() {
+                (Outcome::Passed, elapsed)
+            } else {
+                (Outcome::Failed { actual, diff }, elapsed)
+            }
+        }
+        Ok((Err(message), elapsed)) => (Outcome::Error(message), elapsed),
+        Err(RecvTimeoutError::Timeout) => (Outcome::TimedOut(time_limit), start.elapsed()),
+        Err(RecvTimeoutError::Disconnected) => {
+            let message = match handle.join() {
+                Err(payload) => format!("panicked: {}", panic_message(&*payload)),
+                Ok(()) => "exited without a result".to_string(),
+            };
+            (Outcome::Error(message), start.elapsed())
+        }
+    };
+    CaseResult {
+        exercise: exercise.name.to_string(),
+        case: case.name.clone(),
+        elapsed,
+        outcome,
+    }
+}
+
+/// Runs every case in `file`, or reports each as an error if its exercise is unknown.
+pub fn run_file(file: &CaseFile) -> Vec<CaseResult> {
+    let exercise = exercise::find(&file.exercise);
+    file.cases
+        .iter()
+        .map(|case| match exercise {
+            Some(exercise) => run_case(exercise, case, file.time_limit(case)),
+            None => CaseResult {
+                exercise: file.exercise.clone(),
+                case: case.name.clone(),
+                elapsed: Duration::ZERO,
+                outcome: Outcome::Error(format!("unknown exercise {:?}", file.exercise)),
+            },
+        })
+        .collect()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Where `actual` differs from `expected`, one line per difference, each starting with the
+/// JSON path of the differing value.
+pub fn diff(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    diff_at("$", expected, actual, &mut lines);
+    lines
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, lines: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Array(expected), Value::Array(actual)) => {
+            for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+                diff_at(&format!("{}[{}]", path, i), e, a, lines);
+            }
+            for (i, e) in expected.iter().enumerate().skip(actual.len()) {
+                lines.push(format!("{}[{}]: missing, expected {}", path, i, e));
+            }
+            for (i, a) in actual.iter().enumerate().skip(expected.len()) {
+                lines.push(format!("{}[{}]: unexpected {}", path, i, a));
+            }
+        }
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, e) in expected {
+                match actual.get(key) {
+                    Some(a) => diff_at(&format!("{}.{}", path, key), e, a, lines),
+                    None => lines.push(format!("{}.{}: missing, expected {}", path, key, e)),
+                }
+            }
+            for (key, a) in actual {
+                if !expected.contains_key(key) {
+                    lines.push(format!("{}.{}: unexpected {}", path, key, a));
+                }
+            }
+        }
+        _ if expected != actual => {
+            lines.push(format!("{}: expected {}, got {}", path, expected, actual));
+        }
+        _ => {}
+    }
+}
+
+/// The results of a run.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub results: Vec<CaseResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.results.iter().map(|r| r.elapsed).sum()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            writeln!(f, "{}", result)?;
+        }
+        write!(
+            f,
+            "{} passed, {} failed in {:.3} ms",
+            self.passed(),
+            self.failed(),
+            self.total_time().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn case(input: Value, expected: Value) -> Case {
+        Case {
+            name: "case".into(),
+            input,
+            expected,
+            time_limit_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_paths() {
+        assert!(diff(&json!([6, 3, 6]), &json!([6, 3, 6])).is_empty());
+        assert_eq!(
+            diff(&json!([[1, 6], [8, 10]]), &json!([[1, 5]])),
+            [
+                "$[0][1]: expected 6, got 5",
+                "$[1]: missing, expected [8,10]"
+            ]
+        );
+        assert_eq!(
+            diff(&json!({"a": 1}), &json!({"b": null})),
+            ["$.a: missing, expected 1", "$.b: unexpected null"]
+        );
+        assert_eq!(
+            diff(&json!(null), &json!([2, 7])),
+            ["$: expected null, got [2,7]"]
+        );
+    }
+
+    #[test]
+    fn test_run_case_outcomes() {
+        let lis = exercise::find("length_of_lis").unwrap();
+        let limit = Duration::from_secs(5);
+        let passed = run_case(lis, &case(json!({"nums": [1, 3, 2, 4]}), json!(3)), limit);
+        assert!(passed.passed(), "{}", passed);
+
+        let failed = run_case(lis, &case(json!({"nums": [1, 3, 2, 4]}), json!(4)), limit);
+        assert_eq!(
+            failed.outcome,
+            Outcome::Failed {
+                actual: json!(3),
+                diff: vec!["$: expected 4, got 3".to_string()]
+            }
+        );
+
+        let bad = run_case(lis, &case(json!({"numbers": []}), json!(0)), limit);
+        assert!(matches!(bad.outcome, Outcome::Error(_)));
+
+        let first = Exercise::new("first", "tests", "indexes `nums[0]`", |input| {
+            Ok(input["nums"].as_array().unwrap()[0].clone())
+        });
+        let panicked = run_case(first, &case(json!({"nums": []}), json!(null)), limit);
+        assert!(
+            matches!(&panicked.outcome, Outcome::Error(m) if m.starts_with("panicked")),
+            "{}",
+            panicked
+        );
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let lis = exercise::find("length_of_lis").unwrap();
+        let nums: Vec<i32> = (0..200_000).collect();
+        let result = run_case(
+            lis,
+            &case(json!({ "nums": nums }), json!(200_000)),
+            Duration::ZERO,
+        );
+        assert_eq!(result.outcome, Outcome::TimedOut(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_unknown_exercise() {
+        let file = CaseFile {
+            exercise: "fizz_buzz".into(),
+            time_limit_ms: None,
+            cases: vec![case(json!({}), json!(null))],
+        };
+        let report = Report {
+            results: run_file(&file),
+        };
+        assert_eq!((report.passed(), report.failed()), (0, 1));
+        assert!(report
+            .to_string()
+            .contains("unknown exercise \"fizz_buzz\""));
+    }
+}
@@ -0,0 +1,568 @@
+//! Randomised stress testing of exercises against brute-force oracles.
+//!
+//! Each [`Oracle`] generates random inputs for one exercise and computes the expected
+//! output the slow, obviously correct way. [`stress`] runs the exercise on inputs of
+//! growing size until its output differs from the oracle's, then shrinks that input,
+//! dropping array elements and moving numbers towards zero while the case keeps failing,
+//! and returns the smallest failing case found. [`Failure::save`] writes it as a case
+//! file so it is rerun with the others.
+//!
+//! A run is fully determined by its seed, so a failure can be reproduced from the seed
+//! printed with it.
+
+use crate::cases::{Case, CaseError, CaseFile};
+use crate::exercise::{call, Exercise, Intervals, Nums, NumsTarget};
+use crate::runner::{run_case, Outcome};
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+const MAX_SHRINK_STEPS: usize = 1_000;
+
+/// A xorshift64* generator: small, fast and reproducible from its seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Spread the seed with a splitmix64 step, so nearby seeds give unrelated streams
+        // and the state is never zero.
+        let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    /// A number in `lo..=hi`.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+
+    /// An index in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Random inputs and a brute-force reference implementation for an exercise.
+#[derive(Debug, Clone, Copy)]
+pub struct Oracle {
+    pub exercise: &'static str,
+    pub description: &'static str,
+    generate: fn(&mut Rng, usize) -> Value,
+    brute_force: fn(Value) -> Result<Value, String>,
+    valid: fn(&Value) -> bool,
+}
+
+impl Oracle {
+    /// A random input of roughly `size` elements.
+    pub fn generate(&self, rng: &mut Rng, size: usize) -> Value {
+        (self.generate)(rng, size)
+    }
+
+    /// The expected output for `input`.
+    pub fn expected(&self, input: Value) -> Result<Value, String> {
+        (self.brute_force)(input)
+    }
+
+    /// Whether `input` meets the exercise's preconditions; shrinking only produces inputs
+    /// that do.
+    pub fn is_valid(&self, input: &Value) -> bool {
+        (self.valid)(input)
+    }
+}
+
+/// Between `min_len` and `max_len` numbers in `lo..=hi`.
+fn nums(rng: &mut Rng, min_len: usize, max_len: usize, lo: i64, hi: i64) -> Vec<i64> {
+    let len = min_len + rng.below(max_len - min_len + 1);
+    (0..len).map(|_| rng.range(lo, hi)).collect()
+}
+
+fn any_input(_: &Value) -> bool {
+    true
+}
+
+/// Every oracle, one per exercise.
+pub fn oracles() -> Vec<Oracle> {
+    vec![
+        Oracle {
+            exercise: "two_sum",
+            description: "all pairs, O(n^2)",
+            generate: |rng, size| {
+                let bound = size.max(4) as i64;
+                let nums = nums(rng, 0, size, -bound, bound);
+                // Aim at a pair that exists half the time, or there is rarely an answer.
+                let target = if nums.len() >= 2 && rng.below(2) == 0 {
+                    nums[rng.below(nums.len())] + nums[rng.below(nums.len())]
+                } else {
+                    rng.range(-2 * bound, 2 * bound)
+                };
+                json!({ "nums": nums, "target": target })
+            },
+            brute_force: |input| {
+                call(input, |i: NumsTarget| {
+                    // The first pair to complete, scanning by the later element.
+                    (0..i.nums.len()).find_map(|j| {
+                        (0..j)
+                            .find(|&k| i.nums[k] + i.nums[j] == i.target)
+                            .map(|k| (i.nums[k], i.nums[j]))
+                    })
+                })
+            },
+            valid: any_input,
+        },
+        Oracle {
+            exercise: "max_subarray",
+            description: "all subarrays, O(n^2)",
+            generate: |rng, size| {
+                let bound = size.max(4) as i64;
+                json!({ "nums": nums(rng, 0, size, -bound, bound) })
+            },
+            brute_force: |input| {
+                call(input, |i: Nums| {
+                    // The first subarray with the largest sum, by end and then by start.
+                    let mut best = (*i.nums.first()?, 0, 0);
+                    for end in 0..i.nums.len() {
+                        for start in 0..=end {
+                            let sum: i32 = i.nums[start..=end].iter().sum();
+                            if sum > best.0 {
+                                best = (sum, start, end);
+                            }
+                        }
+                    }
+                    Some(best)
+                })
+            },
+            valid: any_input,
+        },
+        Oracle {
+            exercise: "merge_intervals",
+            description: "merge any overlapping pair until none overlap, O(n^3)",
+            generate: |rng, size| {
+                let intervals: Vec<(i64, i64)> = (0..rng.below(size + 1))
+                    .map(|_| {
+                        let start = rng.range(0, 2 * size as i64);
+                        (start, start + rng.range(0, size as i64 / 2 + 1))
+                    })
+                    .collect();
+                json!({ "intervals": intervals })
+            },
+            brute_force: |input| {
+                call(input, |i: Intervals| {
+                    let mut intervals = i.intervals;
+                    'merge: loop {
+                        for a in 0..intervals.len() {
+                            for b in a + 1..intervals.len() {
+                                let (x, y) = (intervals[a], intervals[b]);
+                                if x.0 <= y.1 && y.0 <= x.1 {
+                                    intervals[a] = (x.0.min(y.0), x.1.max(y.1));
+                                    intervals.swap_remove(b);
+                                    continue 'merge;
+                                }
+                            }
+                        }
+                        break;
+                    }
+                    intervals.sort();
+                    intervals
+                })
+            },
+            valid: |input| {
+                input["intervals"].as_array().is_some_and(|intervals| {
+                    intervals.iter().all(|i| i[0].as_i64() <= i[1].as_i64())
+                })
+            },
+        },
+        Oracle {
+            exercise: "length_of_lis",
+            description: "longest run ending at each element, O(n^2)",
+            generate: |rng, size| json!({ "nums": nums(rng, 0, size, 0, size as i64) }),
+            brute_force: |input| {
+                call(input, |i: Nums| {
+                    let mut longest = vec![1; i.nums.len()];
+                    for j in 0..i.nums.len() {
+                        for k in 0..j {
+                            if i.nums[k] < i.nums[j] {
+                                longest[j] = longest[j].max(longest[k] + 1);
+                            }
+                        }
+                    }
+                    longest.into_iter().max().unwrap_or(0)
+                })
+            },
+            valid: any_input,
+        },
+    ]
+}
+
+/// The oracle for the exercise called `name`.
+pub fn find(name: &str) -> Option<Oracle> {
+    oracles().into_iter().find(|o| o.exercise == name)
+}
+
+/// Smaller variants of `value`, most aggressive first: arrays with chunks removed, then
+/// with each element shrunk, and numbers moved towards zero.
+pub fn shrink(value: &Value) -> Vec<Value> {
+    let mut candidates = Vec::new();
+    match value {
+        Value::Array(items) => {
+            let mut chunk = items.len();
+            while chunk > 0 {
+                for start in (0..items.len()).step_by(chunk) {
+                    let mut smaller = items.clone();
+                    smaller.drain(start..(start + chunk).min(items.len()));
+                    candidates.push(Value::Array(smaller));
+                }
+                chunk /= 2;
+            }
+            for (i, item) in items.iter().enumerate() {
+                for replacement in shrink(item) {
+                    let mut smaller = items.clone();
+                    smaller[i] = replacement;
+                    candidates.push(Value::Array(smaller));
+                }
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                for replacement in shrink(field) {
+                    let mut smaller = fields.clone();
+                    smaller.insert(key.clone(), replacement);
+                    candidates.push(Value::Object(smaller));
+                }
+            }
+        }
+        Value::Number(n) => {
+            if let Some(n) = n.as_i64().filter(|&n| n != 0) {
+                candidates.push(json!(0));
+                if n / 2 != 0 {
+                    candidates.push(json!(n / 2));
+                }
+                if n - n.signum() != n / 2 {
+                    candidates.push(json!(n - n.signum()));
+                }
+            }
+        }
+        _ => {}
+    }
+    candidates
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StressConfig {
+    pub seed: u64,
+    pub iterations: usize,
+    pub max_size: usize,
+    pub time_limit: Duration,
+}
+
+impl StressConfig {
+    /// 1,000 iterations of inputs of up to 32 elements, one second each.
+    pub fn new(seed: u64) -> Self {
+        StressConfig {
+            seed,
+            iterations: 1_000,
+            max_size: 32,
+            time_limit: Duration::from_secs(1),
+        }
+    }
+
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+}
+
+/// An input on which an exercise disagreed with its oracle, and the smallest such input
+/// found by shrinking it.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub exercise: String,
+    pub seed: u64,
+    pub iteration: usize,
+    pub original: Value,
+    pub minimal: Case,
+    pub outcome: Outcome,
+    pub shrink_steps: usize,
+}
+
+impl Failure {
+    pub fn to_case_file(&self) -> CaseFile {
+        CaseFile {
+            exercise: self.exercise.clone(),
+            time_limit_ms: None,
+            cases: vec![self.minimal.clone()],
+        }
+    }
+
+    /// Writes the minimal case to `dir` as a JSON case file named after the exercise and
+    /// seed, and returns its path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, CaseError> {
+        fs::create_dir_all(dir).map_err(|e| CaseError::Io(dir.into(), e))?;
+        let path = dir.join(format!(
+            "{}-seed-{}-{}.json",
+            self.exercise, self.seed, self.iteration
+        ));
+        self.to_case_file().save(&path)?;
+        Ok(path)
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} failed at iteration {} of seed {}",
+            self.exercise, self.iteration, self.seed
+        )?;
+        writeln!(f, "  original input: {}", self.original)?;
+        writeln!(
+            f,
+            "  minimal input:  {} (after {} shrinks)",
+            self.minimal.input, self.shrink_steps
+        )?;
+        write!(f, "  expected:       {}", self.minimal.expected)?;
+        match &self.outcome {
+            Outcome::Failed { actual, .. } => write!(f, "\n  got:            {}", actual),
+            Outcome::TimedOut(limit) => write!(f, "\n  timed out after {:?}", limit),
+            Outcome::Error(message) => write!(f, "\n  {}", message),
+            Outcome::Passed => Ok(()),
+        }
+    }
+}
+
+/// Runs `exercise` against `oracle` on `config.iterations` random inputs, returning the
+/// number of inputs checked, or the first failure once shrunk.
+///
+/// # Panics
+///
+/// Panics if the oracle itself panics or fails to compute an expected output.
+pub fn stress(
+    exercise: Exercise,
+    oracle: &Oracle,
+    config: &StressConfig,
+) -> Result<usize, Box<Failure>> {
+    // Panics in the exercise are reported as failures; the default hook would also print
+    // each one, and shrinking may trigger hundreds. So while this runs, the hook only
+    // passes on panics from this thread. The hook is shared by the whole process, so runs
+    // take turns swapping it.
+    static HOOK: Mutex<()> = Mutex::new(());
+    let _turn = HOOK.lock().unwrap_or_else(PoisonError::into_inner);
+    let caller = thread::current().id();
+    let previous = Arc::new(panic::take_hook());
+    let hook = Arc::clone(&previous);
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() == caller {
+            hook(info);
+        }
+    }));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run_stress(exercise, oracle, config)));
+    // Dropping the filtering hook leaves the only reference to the previous one.
+    let _ = panic::take_hook();
+    if let Ok(previous) = Arc::try_unwrap(previous) {
+        panic::set_hook(previous);
+    }
+    result.unwrap_or_else(|e| panic::resume_unwind(e))
+}
+
+fn run_stress(
+    exercise: Exercise,
+    oracle: &Oracle,
+    config: &StressConfig,
+) -> Result<usize, Box<Failure>> {
+    let mut rng = Rng::new(config.seed);
+    for iteration in 0..config.iterations {
+        // Start small, where failures are easy to read, and grow towards `max_size`.
+        let size = 1 + iteration * config.max_size / config.iterations.max(1);
+        let input = oracle.generate(&mut rng, size);
+        let Some((mut minimal, mut outcome)) = failure(exercise, oracle, &input, config) else {
+            continue;
+        };
+
+        let mut shrink_steps = 0;
+        'shrink: while shrink_steps < MAX_SHRINK_STEPS {
+            for candidate in shrink(&minimal.input) {
+                if !oracle.is_valid(&candidate) {
+                    continue;
+                }
+                if let Some((case, candidate_outcome)) =
+                    failure(exercise, oracle, &candidate, config)
+                {
+                    minimal = case;
+                    outcome = candidate_outcome;
+                    shrink_steps += 1;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        minimal.name = format!("stress seed {} iteration {}", config.seed, iteration);
+        return Err(Box::new(Failure {
+            exercise: exercise.name.to_string(),
+            seed: config.seed,
+            iteration,
+            original: input,
+            minimal,
+            outcome,
+            shrink_steps,
+        }));
+    }
+    Ok(config.iterations)
+}
+
+/// The case and its outcome if `exercise` disagrees with `oracle` on `input`.
+///
+/// # Panics
+///
+/// Panics if the oracle cannot compute the expected output: that is a bug in the harness,
+/// not in the exercise, and skipping the input would report it as a pass.
+fn failure(
+    exercise: Exercise,
+    oracle: &Oracle,
+    input: &Value,
+    config: &StressConfig,
+) -> Option<(Case, Outcome)> {
+    let expected = oracle.expected(input.clone()).unwrap_or_else(|e| {
+        panic!(
+            "the {} oracle failed on input {}: {}",
+            oracle.exercise, input, e
+        )
+    });
+    let case = Case {
+        name: String::new(),
+        input: input.clone(),
+        expected,
+        time_limit_ms: Some(config.time_limit.as_millis() as u64),
+    };
+    let result = run_case(exercise, &case, config.time_limit);
+    (!result.passed()).then_some((case, result.outcome))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exercise;
+    use crate::runner::run_file;
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let draw = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.range(-3, 3)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(7), draw(7));
+        assert_ne!(draw(7), draw(8));
+        assert!(draw(0).iter().all(|n| (-3..=3).contains(n)));
+    }
+
+    #[test]
+    fn test_shrink_candidates() {
+        assert_eq!(shrink(&json!(-6)), [json!(0), json!(-3), json!(-5)]);
+        assert!(shrink(&json!(0)).is_empty());
+        let candidates = shrink(&json!([1, 2]));
+        assert_eq!(candidates[..3], [json!([]), json!([2]), json!([1])]);
+        assert!(candidates.contains(&json!([0, 2])));
+    }
+
+    #[test]
+    fn test_implementations_agree_with_oracles() {
+        for oracle in oracles() {
+            let exercise = exercise::find(oracle.exercise).unwrap();
+            let config = StressConfig::new(42).iterations(300).max_size(24);
+            if let Err(failure) = stress(exercise, &oracle, &config) {
+                panic!("{}", failure);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finds_and_shrinks_a_bug() {
+        // Counts non-decreasing rather than strictly increasing subsequences.
+        let buggy = Exercise::new("length_of_lis", "test", "buggy", |input| {
+            call(input, |i: Nums| {
+                let mut tails: Vec<i32> = Vec::new();
+                for n in i.nums {
+                    let pos = tails.partition_point(|&t| t <= n);
+                    if pos == tails.len() {
+                        tails.push(n);
+                    } else {
+                        tails[pos] = n;
+                    }
+                }
+                tails.len()
+            })
+        });
+        let oracle = find("length_of_lis").unwrap();
+        let failure = stress(buggy, &oracle, &StressConfig::new(1).max_size(40)).unwrap_err();
+        // Shrinking one element at a time cannot turn [2, 2] into [0, 0], but it does get
+        // down to two equal elements.
+        let nums = failure.minimal.input["nums"].as_array().unwrap();
+        assert_eq!(nums.len(), 2);
+        assert_eq!(nums[0], nums[1]);
+        assert_eq!(failure.minimal.expected, json!(1));
+        assert_eq!(
+            failure.outcome,
+            Outcome::Failed {
+                actual: json!(2),
+                diff: vec!["$: expected 1, got 2".to_string()]
+            }
+        );
+
+        // The saved case fails the buggy version and passes the real one.
+        let dir = std::env::temp_dir().join(format!("turing-stress-{}", std::process::id()));
+        let path = failure.save(&dir).unwrap();
+        let file = CaseFile::load(&path).unwrap();
+        assert!(run_file(&file).iter().all(|r| r.passed()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "broken generator")]
+    fn test_panics_in_the_oracle_propagate() {
+        let oracle = Oracle {
+            generate: |_, _| panic!("broken generator"),
+            ..find("two_sum").unwrap()
+        };
+        let _ = stress(
+            exercise::find("two_sum").unwrap(),
+            &oracle,
+            &StressConfig::new(1),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the two_sum oracle failed on input")]
+    fn test_oracle_errors_are_not_passes() {
+        let oracle = Oracle {
+            brute_force: |_| Err("no answer".to_string()),
+            ..find("two_sum").unwrap()
+        };
+        let _ = stress(
+            exercise::find("two_sum").unwrap(),
+            &oracle,
+            &StressConfig::new(1),
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use kadane_max_subarray::{max_subarray, par_max_subarray};
+
+fn nums(n: usize) -> Vec<i64> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % 2001) as i64 - 1000
+        })
+        .collect()
+}
+
+fn bench_sequential_vs_parallel(c: &mut Criterion) {
+    for n in [10_000, 1_000_000, 10_000_000] {
+        let nums = nums(n);
+        let floats: Vec<f64> = nums.iter().map(|&x| x as f64).collect();
+
+        let mut group = c.benchmark_group(format!("max_subarray/{}", n));
+        group.bench_function(BenchmarkId::new("i64", "sequential"), |b| {
+            b.iter(|| max_subarray(black_box(&nums)))
+        });
+        group.bench_function(BenchmarkId::new("i64", "parallel"), |b| {
+            b.iter(|| par_max_subarray(black_box(&nums)))
+        });
+        group.bench_function(BenchmarkId::new("f64", "sequential"), |b| {
+            b.iter(|| max_subarray(black_box(&floats)))
+        });
+        group.bench_function(BenchmarkId::new("f64", "parallel"), |b| {
+            b.iter(|| par_max_subarray(black_box(&floats)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_sequential_vs_parallel);
+criterion_main!(benches);
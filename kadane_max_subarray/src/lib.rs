@@ -1,37 +1,100 @@
+pub mod number;
+pub mod online;
+pub mod parallel;
+pub mod variants;
+
+pub use number::Number;
+pub use online::{OnlineKadane, Segment, SlidingKadane};
+pub use parallel::par_max_subarray;
+pub use variants::{
+    max_circular_subarray, max_product_subarray, max_subarray_with_max_len,
+    max_subarray_with_min_len, max_submatrix, Rect,
+};
+
+use std::error::Error;
+use std::fmt;
+
 /// Finds the contiguous subarray with the maximum sum using Kadane’s Algorithm.
 ///
-/// Given an integer array, this function finds the contiguous subarray with the maximum sum.
-/// Kadane’s Algorithm keeps track of the maximum sum ending at each position.
+/// Given a slice of numbers, this function finds the contiguous subarray with the maximum
+/// sum. Kadane’s Algorithm keeps track of the maximum sum ending at each position. Integer
+/// sums that overflow panic in debug builds and wrap in release builds; use
+/// [`checked_max_subarray`] to detect them, or a wider type.
 ///
 /// # Arguments
 ///
-/// * `nums` - A vector of integers.
+/// * `nums` - A slice of integers, floats or, with the `decimal` feature, decimals.
 ///
 /// # Returns
 ///
-/// The maximum sum of the contiguous subarray.
+/// The maximum sum of a contiguous subarray with the indices of its first and last elements,
+/// or `None` if `nums` is empty. Of subarrays with equal sums, the one ending first wins,
+/// then the one starting first.
 ///
 /// # Examples
 ///
 /// ```
-/// let nums = vec![-2, 1, -3, 4, -1, 2, 1, -5, 4];
 /// use kadane_max_subarray::max_subarray;
-/// assert_eq!(kadane_max_subarray::max_subarray(nums), (6, 3, 6));
+///
+/// assert_eq!(max_subarray(&[-2, 1, -3, 4, -1, 2, 1, -5, 4]), Some((6, 3, 6)));
+/// assert_eq!(max_subarray(&[1.5, -0.5, 2.0]), Some((3.0, 0, 2)));
+/// assert_eq!(max_subarray::<i64>(&[]), None);
 /// ```
-pub fn max_subarray(nums: Vec<i32>) -> (i32, usize, usize) {
-    let mut max_current = nums[0];
-    let mut max_global = nums[0];
+pub fn max_subarray<N: Number>(nums: &[N]) -> Option<(N, usize, usize)> {
+    // An unchecked sum never reports an overflow.
+    kadane(nums, |a, b| Some(a + b)).unwrap_or(None)
+}
+
+/// Like [`max_subarray`], but fails instead of overflowing.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::checked_max_subarray;
+///
+/// assert_eq!(checked_max_subarray(&[3i32, -1, 2]), Ok(Some((4, 0, 2))));
+/// let err = checked_max_subarray(&[i32::MAX, 1]).unwrap_err();
+/// assert_eq!(err.index, 1);
+/// ```
+pub fn checked_max_subarray<N: Number>(nums: &[N]) -> Result<Option<(N, usize, usize)>, Overflow> {
+    kadane(nums, N::checked_add)
+}
+
+/// The running sum overflowed on adding the element at `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub index: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "subarray sum overflowed at index {}", self.index)
+    }
+}
+
+impl Error for Overflow {}
+
+fn kadane<N: Number>(
+    nums: &[N],
+    add: impl Fn(N, N) -> Option<N>,
+) -> Result<Option<(N, usize, usize)>, Overflow> {
+    let Some(&first) = nums.first() else {
+        return Ok(None);
+    };
+    let mut max_current = first;
+    let mut max_global = first;
     let mut start = 0;
     let mut end = 0;
     let mut temp_start = 0;
 
-    // Iterate over the elements of the vector starting from the second element
+    // Iterate over the elements of the slice starting from the second element
     for (i, &num) in nums.iter().enumerate().skip(1) {
-        if num > max_current + num {
+        // Starting afresh beats extending a run with a negative sum
+        if max_current < N::ZERO {
             max_current = num;
             temp_start = i;
         } else {
-            max_current += num;
+            max_current = add(max_current, num).ok_or(Overflow { index: i })?;
         }
 
         if max_current > max_global {
@@ -41,7 +104,7 @@ pub fn max_subarray(nums: Vec<i32>) -> (i32, usize, usize) {
         }
     }
     // Return the maximum sum found along with the start and end indices
-    (max_global, start, end)
+    Ok(Some((max_global, start, end)))
 }
 
 #[cfg(test)]
@@ -50,6 +113,47 @@ mod tests {
 
     #[test]
     fn test_max_subarray() {
-        assert_eq!(max_subarray(vec![-2, 1, -3, 4, -1, 2, 1, -5, 4]), (6, 3, 6));
+        assert_eq!(
+            max_subarray(&[-2, 1, -3, 4, -1, 2, 1, -5, 4]),
+            Some((6, 3, 6))
+        );
+        assert_eq!(max_subarray::<i32>(&[]), None);
+        assert_eq!(max_subarray(&[-3, -1, -2]), Some((-1, 1, 1)));
+    }
+
+    #[test]
+    fn test_wide_and_float_sums() {
+        let big = [i64::from(i32::MAX), i64::from(i32::MAX), -1];
+        assert_eq!(max_subarray(&big), Some((2 * i64::from(i32::MAX), 0, 1)));
+        assert_eq!(max_subarray(&[0.5f64, -2.0, 0.25, 0.5]), Some((0.75, 2, 3)));
+    }
+
+    #[test]
+    fn test_checked_max_subarray() {
+        assert_eq!(checked_max_subarray::<i8>(&[]), Ok(None));
+        assert_eq!(
+            checked_max_subarray(&[100i8, 27, -5]),
+            Ok(Some((127, 0, 1)))
+        );
+        assert_eq!(
+            checked_max_subarray(&[100i8, 20, 10]),
+            Err(Overflow { index: 2 })
+        );
+        assert_eq!(
+            checked_max_subarray(&[f64::MAX, f64::MAX]),
+            Err(Overflow { index: 1 })
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal() {
+        use rust_decimal::Decimal;
+
+        let nums: Vec<Decimal> = ["0.10", "-0.30", "0.20", "0.15", "-0.05"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(max_subarray(&nums), Some(("0.35".parse().unwrap(), 2, 3)));
     }
 }
@@ -0,0 +1,59 @@
+//! The numeric types `max_subarray` and `par_max_subarray` work over.
+//!
+//! Implemented for the primitive integers and floats, and for `rust_decimal::Decimal` with
+//! the `decimal` feature. Floats compare with `PartialOrd`, so a NaN in the input gives an
+//! unspecified (but not panicking) result.
+
+use std::ops::Add;
+
+/// A type whose values can be summed and compared.
+pub trait Number: Copy + PartialOrd + Add<Output = Self> {
+    const ZERO: Self;
+
+    /// `self + other`, or `None` if the sum cannot be represented. Floats overflow when two
+    /// finite values sum to an infinity.
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                const ZERO: Self = 0;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! impl_float {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                const ZERO: Self = 0.0;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    let sum = self + other;
+                    let overflowed = sum.is_infinite() && self.is_finite() && other.is_finite();
+                    (!overflowed).then_some(sum)
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+#[cfg(feature = "decimal")]
+impl Number for rust_decimal::Decimal {
+    const ZERO: Self = rust_decimal::Decimal::ZERO;
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        rust_decimal::Decimal::checked_add(self, other)
+    }
+}
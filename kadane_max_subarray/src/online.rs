@@ -0,0 +1,266 @@
+//! Maximum subarray over a stream of timestamped values.
+//!
+//! [`OnlineKadane`] finds the best segment of everything seen so far in O(1) time and
+//! space per value. [`SlidingKadane`] finds the best segment among the last `window`
+//! values: a segment's sums combine associatively, so it keeps the window as a queue of
+//! two stacks of running combinations, giving amortised O(1) per value.
+//!
+//! # Examples
+//!
+//! ```
+//! use kadane_max_subarray::{OnlineKadane, SlidingKadane};
+//!
+//! let pnl = [("09:00", 3), ("10:00", -5), ("11:00", 4), ("12:00", 2), ("13:00", -1)];
+//! let mut all_day = OnlineKadane::new();
+//! let mut last_three = SlidingKadane::new(3);
+//! for (time, value) in pnl {
+//!     all_day.push(time, value);
+//!     last_three.push(time, value);
+//! }
+//! let best = all_day.best().unwrap();
+//! assert_eq!((best.sum, best.start, best.end), (6, "11:00", "12:00"));
+//! let recent = last_three.best().unwrap();
+//! assert_eq!((recent.sum, recent.start_index, recent.end_index), (6, 2, 3));
+//! ```
+
+use crate::parallel::Summary;
+use std::collections::VecDeque;
+
+/// A contiguous run of the stream: its sum, and the timestamps and positions in the
+/// stream of its first and last values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<T> {
+    pub sum: i64,
+    pub start: T,
+    pub end: T,
+    pub start_index: usize,
+    pub end_index: usize,
+}
+
+/// Kadane's algorithm applied one value at a time.
+#[derive(Debug, Clone)]
+pub struct OnlineKadane<T> {
+    len: usize,
+    /// The sum, start index and start timestamp of the best run ending at the last value.
+    current: Option<(i64, usize, T)>,
+    best: Option<Segment<T>>,
+}
+
+impl<T: Clone> Default for OnlineKadane<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> OnlineKadane<T> {
+    pub fn new() -> Self {
+        OnlineKadane {
+            len: 0,
+            current: None,
+            best: None,
+        }
+    }
+
+    /// The number of values seen.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds the next value and returns the best segment so far.
+    pub fn push(&mut self, timestamp: T, value: i64) -> &Segment<T> {
+        let index = self.len;
+        self.len += 1;
+        let current = match self.current.take() {
+            // Like `max_subarray`, extend the run unless restarting is strictly better.
+            Some((sum, start, start_time)) if sum >= 0 => (sum + value, start, start_time),
+            _ => (value, index, timestamp.clone()),
+        };
+        if self.best.as_ref().is_none_or(|best| current.0 > best.sum) {
+            self.best = Some(Segment {
+                sum: current.0,
+                start: current.2.clone(),
+                end: timestamp,
+                start_index: current.1,
+                end_index: index,
+            });
+        }
+        self.current = Some(current);
+        self.best.as_ref().unwrap()
+    }
+
+    /// The best segment so far, or `None` before the first value.
+    pub fn best(&self) -> Option<&Segment<T>> {
+        self.best.as_ref()
+    }
+
+    /// The sum of the best segment ending at the last value.
+    pub fn current_sum(&self) -> Option<i64> {
+        self.current.as_ref().map(|c| c.0)
+    }
+}
+
+/// The best segment among the last `window` values of a stream.
+#[derive(Debug, Clone)]
+pub struct SlidingKadane<T> {
+    window: usize,
+    next_index: usize,
+    timestamps: VecDeque<T>,
+    /// Older values, each entry summarising itself and everything newer in this stack; the
+    /// oldest value is on top.
+    front: Vec<Summary<i64>>,
+    /// Newer values in arrival order, with a summary of the whole stack.
+    back: Vec<(i64, usize)>,
+    back_summary: Option<Summary<i64>>,
+}
+
+impl<T: Clone> SlidingKadane<T> {
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window must hold at least one value");
+        SlidingKadane {
+            window,
+            next_index: 0,
+            timestamps: VecDeque::with_capacity(window),
+            front: Vec::new(),
+            back: Vec::new(),
+            back_summary: None,
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// The number of values in the window.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Adds the next value, dropping the oldest if the window is full, and returns the
+    /// best segment in the window.
+    pub fn push(&mut self, timestamp: T, value: i64) -> Segment<T> {
+        if self.len() == self.window {
+            self.pop_oldest();
+        }
+        let index = self.next_index;
+        self.next_index += 1;
+        self.timestamps.push_back(timestamp);
+        self.back.push((value, index));
+        let leaf = Summary::leaf(value, index);
+        self.back_summary = Some(match &self.back_summary {
+            Some(summary) => Summary::combine(summary, &leaf),
+            None => leaf,
+        });
+        self.best().unwrap()
+    }
+
+    /// The best segment in the window, or `None` before the first value.
+    pub fn best(&self) -> Option<Segment<T>> {
+        let summary = match (self.front.last(), &self.back_summary) {
+            (Some(front), Some(back)) => Summary::combine(front, back),
+            (Some(only), None) | (None, Some(only)) => *only,
+            (None, None) => return None,
+        };
+        let (sum, start_index, end_index) = summary.best;
+        let first = self.next_index - self.len();
+        Some(Segment {
+            sum,
+            start: self.timestamps[start_index - first].clone(),
+            end: self.timestamps[end_index - first].clone(),
+            start_index,
+            end_index,
+        })
+    }
+
+    fn pop_oldest(&mut self) {
+        if self.front.is_empty() {
+            // Each value moves stacks at most once, which is what makes pushes amortised
+            // O(1).
+            for &(value, index) in self.back.iter().rev() {
+                let leaf = Summary::leaf(value, index);
+                self.front.push(match self.front.last() {
+                    Some(newer) => Summary::combine(&leaf, newer),
+                    None => leaf,
+                });
+            }
+            self.back.clear();
+            self.back_summary = None;
+        }
+        self.front.pop();
+        self.timestamps.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(seed: u64, len: usize) -> Vec<i64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 21) as i64 - 10
+            })
+            .collect()
+    }
+
+    fn brute_force(values: &[i64]) -> i64 {
+        (0..values.len())
+            .flat_map(|s| (s..values.len()).map(move |e| values[s..=e].iter().sum()))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_online_matches_max_subarray() {
+        let stream = values(3, 500);
+        let mut online = OnlineKadane::new();
+        assert!(online.best().is_none());
+        for (i, &value) in stream.iter().enumerate() {
+            let best = online.push(i * 10, value).clone();
+            let (sum, start, end) = crate::max_subarray(&stream[..=i]).unwrap();
+            assert_eq!(
+                (best.sum, best.start_index, best.end_index),
+                (sum, start, end)
+            );
+            assert_eq!((best.start, best.end), (start * 10, end * 10));
+        }
+        assert_eq!(online.len(), 500);
+    }
+
+    #[test]
+    fn test_sliding_matches_brute_force() {
+        for window in [1, 2, 5, 16] {
+            let stream = values(window as u64, 300);
+            let mut sliding = SlidingKadane::new(window);
+            for (i, &value) in stream.iter().enumerate() {
+                let best = sliding.push(format!("t{}", i), value);
+                let first = (i + 1).saturating_sub(window);
+                assert_eq!(
+                    best.sum,
+                    brute_force(&stream[first..=i]),
+                    "window {}",
+                    window
+                );
+                assert!(first <= best.start_index && best.end_index <= i);
+                let segment: i64 = stream[best.start_index..=best.end_index].iter().sum();
+                assert_eq!(segment, best.sum);
+                assert_eq!(best.start, format!("t{}", best.start_index));
+            }
+            assert_eq!(sliding.len(), window);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Divide-and-conquer maximum subarray across threads.
+//!
+//! A run of values is summarised by its total, its best prefix, its best suffix and its best
+//! subarray. Two adjacent summaries combine into the summary of their concatenation: the
+//! best subarray lies in the left run, in the right run, or spans the boundary as a left
+//! suffix followed by a right prefix. So the input can be split in halves, summarised on
+//! separate threads and merged back up.
+//!
+//! Ties are broken as [`max_subarray`](crate::max_subarray) breaks them, earliest end and
+//! then earliest start, so the two agree exactly on integers whenever the sum of every
+//! subarray of the input fits in the type, which holds if the sum of the absolute values
+//! does. Unlike the sequential scan, the summaries add up whole runs, so an input whose
+//! best subarray fits but whose total does not can overflow here. Float sums are added in
+//! a different order and may differ in the last bits.
+
+use crate::number::Number;
+use std::panic;
+use std::thread;
+
+/// Inputs shorter than this are not worth another thread.
+const MIN_CHUNK: usize = 1 << 14;
+
+/// Like [`max_subarray`](crate::max_subarray), but splits the work across the available
+/// cores.
+///
+/// The sum of every subarray of the input, not just the best one, must fit in `N`. An
+/// integer overflow panics in debug builds and gives a wrong answer in release builds; use
+/// [`checked_max_subarray`](crate::checked_max_subarray) with a wider type if that is a
+/// risk.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::{max_subarray, par_max_subarray};
+///
+/// let nums: Vec<i64> = (0..100_000).map(|i| (i * 7919 % 201) - 100).collect();
+/// assert_eq!(par_max_subarray(&nums), max_subarray(&nums));
+/// assert_eq!(par_max_subarray::<f64>(&[]), None);
+/// ```
+pub fn par_max_subarray<N: Number + Send + Sync>(nums: &[N]) -> Option<(N, usize, usize)> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    with_threads(nums, threads, MIN_CHUNK)
+}
+
+fn with_threads<N: Number + Send + Sync>(
+    nums: &[N],
+    threads: usize,
+    min_chunk: usize,
+) -> Option<(N, usize, usize)> {
+    let min_chunk = min_chunk.max(1);
+    if threads <= 1 || nums.len() < 2 * min_chunk {
+        // Summarising does more work per value than Kadane's algorithm alone.
+        return crate::max_subarray(nums);
+    }
+    Some(summarise(nums, 0, threads, min_chunk).best)
+}
+
+fn summarise<N: Number + Send + Sync>(
+    nums: &[N],
+    offset: usize,
+    threads: usize,
+    min_chunk: usize,
+) -> Summary<N> {
+    if threads <= 1 || nums.len() < 2 * min_chunk {
+        return Summary::of(nums, offset);
+    }
+    let mid = nums.len() / 2;
+    let (left, right) = nums.split_at(mid);
+    let (left, right) = thread::scope(|scope| {
+        let left = scope.spawn(|| summarise(left, offset, threads / 2, min_chunk));
+        let right = summarise(right, offset + mid, threads - threads / 2, min_chunk);
+        let left = left.join().unwrap_or_else(|e| panic::resume_unwind(e));
+        (left, right)
+    });
+    Summary::combine(&left, &right)
+}
+
+/// What is needed to combine a non-empty run of values with its neighbours. Positions are
+/// indices into the whole input.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Summary<N> {
+    pub total: N,
+    /// Sum and end index.
+    pub prefix: (N, usize),
+    /// Sum and start index.
+    pub suffix: (N, usize),
+    /// Sum, start and end index.
+    pub best: (N, usize, usize),
+}
+
+impl<N: Number> Summary<N> {
+    pub fn leaf(value: N, index: usize) -> Self {
+        Summary {
+            total: value,
+            prefix: (value, index),
+            suffix: (value, index),
+            best: (value, index, index),
+        }
+    }
+
+    /// Summarises `nums`, whose first value is at `offset`, in a single pass for the
+    /// prefix and best subarray and one back for the suffix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nums` is empty.
+    pub fn of(nums: &[N], offset: usize) -> Self {
+        let mut total = nums[0];
+        let mut prefix = (nums[0], offset);
+        let mut current = (nums[0], offset);
+        let mut best = (nums[0], offset, offset);
+        for (i, &num) in nums.iter().enumerate().skip(1) {
+            let index = offset + i;
+            total = total + num;
+            if total > prefix.0 {
+                prefix = (total, index);
+            }
+            current = if current.0 < N::ZERO {
+                (num, index)
+            } else {
+                (current.0 + num, current.1)
+            };
+            if current.0 > best.0 {
+                best = (current.0, current.1, index);
+            }
+        }
+
+        let last = nums.len() - 1;
+        let mut running = nums[last];
+        let mut suffix = (running, offset + last);
+        for (i, &num) in nums.iter().enumerate().rev().skip(1) {
+            running = running + num;
+            if running >= suffix.0 {
+                suffix = (running, offset + i);
+            }
+        }
+
+        Summary {
+            total,
+            prefix,
+            suffix,
+            best,
+        }
+    }
+
+    /// The summary of `left` followed immediately by `right`.
+    pub fn combine(left: &Self, right: &Self) -> Self {
+        let extended_prefix = left.total + right.prefix.0;
+        let extended_suffix = right.total + left.suffix.0;
+        let spanning = (
+            left.suffix.0 + right.prefix.0,
+            left.suffix.1,
+            right.prefix.1,
+        );
+        // Among equal sums, the earliest end and then the earliest start wins.
+        let mut best = left.best;
+        for candidate in [spanning, right.best] {
+            if candidate.0 > best.0
+                || (candidate.0 == best.0 && (candidate.2, candidate.1) < (best.2, best.1))
+            {
+                best = candidate;
+            }
+        }
+        Summary {
+            total: left.total + right.total,
+            prefix: if extended_prefix > left.prefix.0 {
+                (extended_prefix, right.prefix.1)
+            } else {
+                left.prefix
+            },
+            // Prefer the longer suffix, as Kadane's algorithm keeps extending a run whose
+            // sum is zero.
+            suffix: if extended_suffix >= right.suffix.0 {
+                (extended_suffix, left.suffix.1)
+            } else {
+                right.suffix
+            },
+            best,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::max_subarray;
+
+    fn values(seed: u64, len: usize, range: u64) -> Vec<i64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % (2 * range + 1)) as i64 - range as i64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_sequential_including_ties() {
+        // A narrow range makes equal sums, and so tie-breaking, common.
+        for seed in 1..200 {
+            let nums = values(seed, seed as usize % 64 + 1, 2);
+            for (threads, min_chunk) in [(2, 1), (3, 2), (8, 1), (16, 5)] {
+                assert_eq!(
+                    with_threads(&nums, threads, min_chunk),
+                    max_subarray(&nums),
+                    "{:?} on {} threads",
+                    nums,
+                    threads
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_input_and_floats() {
+        let nums = values(42, 200_000, 1000);
+        assert_eq!(with_threads(&nums, 8, 1024), max_subarray(&nums));
+        assert_eq!(par_max_subarray(&nums), max_subarray(&nums));
+
+        let floats: Vec<f64> = nums.iter().map(|&n| n as f64 / 4.0).collect();
+        let (sum, start, end) = with_threads(&floats, 8, 1024).unwrap();
+        let expected = max_subarray(&nums).unwrap();
+        assert_eq!(
+            (sum, start, end),
+            (expected.0 as f64 / 4.0, expected.1, expected.2)
+        );
+        assert_eq!(with_threads::<i64>(&[], 8, 1), None);
+    }
+
+    #[test]
+    fn test_agrees_while_running_sums_fit() {
+        // Every subarray sums to -1, 0 or 1.
+        let nums: Vec<i8> = (0..240).map(|i| if i % 2 == 0 { -1 } else { 1 }).collect();
+        assert_eq!(with_threads(&nums, 8, 4), max_subarray(&nums));
+        let nums = [-15i8; 8];
+        assert_eq!(with_threads(&nums, 2, 1), Some((-15, 0, 0)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "overflow")]
+    fn test_total_that_overflows_panics_in_debug() {
+        // The sequential scan never adds these up; the summaries do.
+        assert_eq!(max_subarray(&[-100i8; 8]), Some((-100, 0, 0)));
+        with_threads(&[-100i8; 8], 2, 1);
+    }
+}
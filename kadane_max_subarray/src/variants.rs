@@ -0,0 +1,362 @@
+//! Variations on the maximum subarray problem.
+//!
+//! Like [`max_subarray`](crate::max_subarray), each function returns the best sum (or
+//! product) together with the inclusive start and end indices of the subarray achieving
+//! it. They take a slice and return `None` where there is no valid subarray, such as for
+//! an empty input.
+
+use std::collections::VecDeque;
+
+/// An inclusive range of rows and columns of a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+}
+
+/// Kadane's algorithm for the subarray whose sum is best according to `better`.
+fn kadane(nums: &[i32], better: fn(i32, i32) -> bool) -> Option<(i32, usize, usize)> {
+    let (&first, rest) = nums.split_first()?;
+    let mut current = (first, 0);
+    let mut best = (first, 0, 0);
+    for (i, &num) in rest.iter().enumerate().map(|(i, n)| (i + 1, n)) {
+        // Extend the current run unless starting afresh at `num` is strictly better.
+        current = if better(num, current.0 + num) {
+            (num, i)
+        } else {
+            (current.0 + num, current.1)
+        };
+        if better(current.0, best.0) {
+            best = (current.0, current.1, i);
+        }
+    }
+    Some(best)
+}
+
+/// The maximum subarray sum when the array is treated as circular, so a subarray may run
+/// off the end and continue from the start.
+///
+/// Returns `(sum, start, end)`; when `start > end` the subarray is `nums[start..]`
+/// followed by `nums[..=end]`.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::max_circular_subarray;
+///
+/// assert_eq!(max_circular_subarray(&[5, -3, 5]), Some((10, 2, 0)));
+/// assert_eq!(max_circular_subarray(&[-3, -2, -3]), Some((-2, 1, 1)));
+/// ```
+pub fn max_circular_subarray(nums: &[i32]) -> Option<(i32, usize, usize)> {
+    let linear = kadane(nums, |a, b| a > b)?;
+    let (min, min_start, min_end) = kadane(nums, |a, b| a < b)?;
+    let n = nums.len();
+    if min_start == 0 && min_end == n - 1 {
+        // Every element is needed for the minimum, so nothing is left to wrap around it.
+        return Some(linear);
+    }
+    // The best wrapping subarray is everything except the minimum subarray.
+    let wrapped = nums.iter().sum::<i32>() - min;
+    if wrapped > linear.0 {
+        Some((wrapped, (min_end + 1) % n, (min_start + n - 1) % n))
+    } else {
+        Some(linear)
+    }
+}
+
+/// The rectangle of `matrix` with the largest sum, found by running Kadane's algorithm
+/// over the column sums of every pair of top and bottom rows in O(rows² × columns).
+///
+/// Returns `None` if the matrix has no rows or no columns.
+///
+/// # Panics
+///
+/// Panics if the rows are not all the same length.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::{max_submatrix, Rect};
+///
+/// let matrix = [
+///     vec![1, -9, 2],
+///     vec![-1, 4, 3],
+///     vec![-8, 5, -1],
+/// ];
+/// let rect = Rect { top: 1, left: 1, bottom: 2, right: 2 };
+/// assert_eq!(max_submatrix(&matrix), Some((11, rect)));
+/// ```
+pub fn max_submatrix<R: AsRef<[i32]>>(matrix: &[R]) -> Option<(i32, Rect)> {
+    let columns = matrix.first()?.as_ref().len();
+    assert!(
+        matrix.iter().all(|row| row.as_ref().len() == columns),
+        "matrix rows must all have the same length"
+    );
+    let mut best: Option<(i32, Rect)> = None;
+    let mut column_sums = vec![0; columns];
+    for top in 0..matrix.len() {
+        column_sums.iter_mut().for_each(|sum| *sum = 0);
+        for (bottom, row) in matrix.iter().enumerate().skip(top) {
+            for (sum, &value) in column_sums.iter_mut().zip(row.as_ref()) {
+                *sum += value;
+            }
+            // Only `None` when there are no columns.
+            let (sum, left, right) = kadane(&column_sums, |a, b| a > b)?;
+            if best.is_none_or(|(best, _)| sum > best) {
+                let rect = Rect {
+                    top,
+                    left,
+                    bottom,
+                    right,
+                };
+                best = Some((sum, rect));
+            }
+        }
+    }
+    best
+}
+
+/// The maximum sum of a subarray at least `k` elements long, in O(n).
+///
+/// A `k` of zero allows any non-empty subarray. Returns `None` if `nums` has fewer than
+/// `max(k, 1)` elements.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::max_subarray_with_min_len;
+///
+/// let nums = [5, -10, 2, 3];
+/// assert_eq!(max_subarray_with_min_len(&nums, 1), Some((5, 0, 0)));
+/// assert_eq!(max_subarray_with_min_len(&nums, 3), Some((0, 0, 3)));
+/// ```
+pub fn max_subarray_with_min_len(nums: &[i32], k: usize) -> Option<(i32, usize, usize)> {
+    let k = k.max(1);
+    if nums.len() < k {
+        return None;
+    }
+    let prefix = prefix_sums(nums);
+    // The smallest prefix sum before a start at least `k` elements back from `end`.
+    let mut min_start = 0;
+    let mut best: Option<(i32, usize, usize)> = None;
+    for end in k - 1..nums.len() {
+        let start = end + 1 - k;
+        if prefix[start] < prefix[min_start] {
+            min_start = start;
+        }
+        let sum = prefix[end + 1] - prefix[min_start];
+        if best.is_none_or(|(best, _, _)| sum > best) {
+            best = Some((sum, min_start, end));
+        }
+    }
+    best
+}
+
+/// The maximum sum of a non-empty subarray at most `k` elements long, in O(n).
+///
+/// For each end, the best start is the one with the smallest prefix sum among the last
+/// `k`; a deque of candidate starts with increasing prefix sums yields it in amortised
+/// O(1). Returns `None` if `nums` is empty or `k` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::max_subarray_with_max_len;
+///
+/// let nums = [4, -1, 4, -1, 4];
+/// assert_eq!(max_subarray_with_max_len(&nums, 5), Some((10, 0, 4)));
+/// assert_eq!(max_subarray_with_max_len(&nums, 2), Some((4, 0, 0)));
+/// ```
+pub fn max_subarray_with_max_len(nums: &[i32], k: usize) -> Option<(i32, usize, usize)> {
+    if nums.is_empty() || k == 0 {
+        return None;
+    }
+    let prefix = prefix_sums(nums);
+    let mut starts: VecDeque<usize> = VecDeque::new();
+    let mut best: Option<(i32, usize, usize)> = None;
+    for end in 0..nums.len() {
+        // A start with a prefix sum no smaller than this one's is never better again.
+        while starts.back().is_some_and(|&s| prefix[s] >= prefix[end]) {
+            starts.pop_back();
+        }
+        starts.push_back(end);
+        if starts.front().is_some_and(|&s| s + k <= end) {
+            starts.pop_front();
+        }
+        let start = starts[0];
+        let sum = prefix[end + 1] - prefix[start];
+        if best.is_none_or(|(best, _, _)| sum > best) {
+            best = Some((sum, start, end));
+        }
+    }
+    best
+}
+
+fn prefix_sums(nums: &[i32]) -> Vec<i32> {
+    let mut prefix = Vec::with_capacity(nums.len() + 1);
+    prefix.push(0);
+    for &num in nums {
+        prefix.push(prefix[prefix.len() - 1] + num);
+    }
+    prefix
+}
+
+/// The maximum product of a non-empty subarray, tracking both the largest and the smallest
+/// product ending at each element, since a negative number swaps them.
+///
+/// Products are `i64`; like other arithmetic they overflow on long runs of large values.
+///
+/// # Examples
+///
+/// ```
+/// use kadane_max_subarray::max_product_subarray;
+///
+/// assert_eq!(max_product_subarray(&[2, 3, -2, 4]), Some((6, 0, 1)));
+/// assert_eq!(max_product_subarray(&[-2, 3, -4]), Some((24, 0, 2)));
+/// ```
+pub fn max_product_subarray(nums: &[i32]) -> Option<(i64, usize, usize)> {
+    let (&first, rest) = nums.split_first()?;
+    // (product, start) of the largest and smallest products ending at the current element.
+    let mut high = (first as i64, 0);
+    let mut low = high;
+    let mut best = (first as i64, 0, 0);
+    for (i, &num) in rest.iter().enumerate().map(|(i, n)| (i + 1, n)) {
+        let num = num as i64;
+        let candidates = [(num, i), (high.0 * num, high.1), (low.0 * num, low.1)];
+        high = *candidates.iter().max_by_key(|c| c.0).unwrap();
+        low = *candidates.iter().min_by_key(|c| c.0).unwrap();
+        if high.0 > best.0 {
+            best = (high.0, high.1, i);
+        }
+    }
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn range(&mut self, lo: i32, hi: i32) -> i32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            lo + (self.0 % (hi - lo + 1) as u64) as i32
+        }
+
+        fn nums(&mut self, max_len: i32, bound: i32) -> Vec<i32> {
+            let len = self.range(0, max_len);
+            (0..len).map(|_| self.range(-bound, bound)).collect()
+        }
+    }
+
+    /// The best sum of any subarray whose length `fits`.
+    fn brute_force(nums: &[i32], fits: impl Fn(usize) -> bool) -> Option<i32> {
+        (0..nums.len())
+            .flat_map(|s| (s..nums.len()).map(move |e| (s, e)))
+            .filter(|&(s, e)| fits(e - s + 1))
+            .map(|(s, e)| nums[s..=e].iter().sum())
+            .max()
+    }
+
+    /// Checks that `result` is the sum of its range, and that the range's length fits.
+    fn check(nums: &[i32], result: Option<(i32, usize, usize)>, fits: impl Fn(usize) -> bool) {
+        assert_eq!(result.map(|r| r.0), brute_force(nums, &fits), "{:?}", nums);
+        if let Some((sum, start, end)) = result {
+            assert_eq!(nums[start..=end].iter().sum::<i32>(), sum, "{:?}", nums);
+            assert!(fits(end - start + 1), "{:?}", nums);
+        }
+    }
+
+    #[test]
+    fn test_length_constraints_match_brute_force() {
+        let mut rng = XorShift(0x2545f4914f6cdd1d);
+        for _ in 0..2_000 {
+            let nums = rng.nums(12, 10);
+            let k = rng.range(0, 6) as usize;
+            check(&nums, max_subarray_with_min_len(&nums, k), |len| {
+                len >= k.max(1)
+            });
+            check(&nums, max_subarray_with_max_len(&nums, k), |len| len <= k);
+        }
+        assert_eq!(max_subarray_with_min_len(&[1, 2], 3), None);
+        assert_eq!(max_subarray_with_max_len(&[1, 2], 0), None);
+    }
+
+    #[test]
+    fn test_circular_matches_brute_force() {
+        let mut rng = XorShift(42);
+        for _ in 0..2_000 {
+            let nums = rng.nums(10, 10);
+            let result = max_circular_subarray(&nums);
+            // Every rotation of the array, with subarrays no longer than the array.
+            let doubled = [nums.as_slice(), nums.as_slice()].concat();
+            let expected = brute_force(&doubled, |len| len <= nums.len());
+            assert_eq!(result.map(|r| r.0), expected, "{:?}", nums);
+            if let Some((sum, start, end)) = result {
+                let len = (end + nums.len() - start) % nums.len() + 1;
+                assert_eq!(doubled[start..start + len].iter().sum::<i32>(), sum);
+            }
+        }
+        assert_eq!(max_circular_subarray(&[]), None);
+    }
+
+    #[test]
+    fn test_submatrix_matches_brute_force() {
+        let mut rng = XorShift(7);
+        for _ in 0..300 {
+            let (rows, columns) = (rng.range(1, 5) as usize, rng.range(1, 5) as usize);
+            let matrix: Vec<Vec<i32>> = (0..rows)
+                .map(|_| (0..columns).map(|_| rng.range(-9, 9)).collect())
+                .collect();
+            let area = |r: Rect| -> i32 {
+                matrix[r.top..=r.bottom]
+                    .iter()
+                    .map(|row| row[r.left..=r.right].iter().sum::<i32>())
+                    .sum()
+            };
+            let mut expected = i32::MIN;
+            for top in 0..rows {
+                for bottom in top..rows {
+                    for left in 0..columns {
+                        for right in left..columns {
+                            expected = expected.max(area(Rect {
+                                top,
+                                left,
+                                bottom,
+                                right,
+                            }));
+                        }
+                    }
+                }
+            }
+            let (sum, rect) = max_submatrix(&matrix).unwrap();
+            assert_eq!((sum, area(rect)), (expected, expected), "{:?}", matrix);
+        }
+        assert_eq!(max_submatrix::<Vec<i32>>(&[]), None);
+        assert_eq!(max_submatrix(&[Vec::<i32>::new()]), None);
+    }
+
+    #[test]
+    fn test_product_matches_brute_force() {
+        let mut rng = XorShift(99);
+        for _ in 0..2_000 {
+            let nums = rng.nums(10, 4);
+            let product = |s: usize, e: usize| nums[s..=e].iter().map(|&n| n as i64).product();
+            let expected = (0..nums.len())
+                .flat_map(|s| (s..nums.len()).map(move |e| (s, e)))
+                .map(|(s, e)| product(s, e))
+                .max();
+            let result = max_product_subarray(&nums);
+            assert_eq!(result.map(|r| r.0), expected, "{:?}", nums);
+            if let Some((value, start, end)) = result {
+                assert_eq!(product(start, end), value, "{:?}", nums);
+            }
+        }
+        assert_eq!(max_product_subarray(&[0, -1]), Some((0, 0, 0)));
+    }
+}